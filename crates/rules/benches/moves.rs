@@ -0,0 +1,89 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+use rules::{rng::RngConfig, Bitboard, Direction, Game, GameConfig, MoveOutcome, Number, Position};
+
+/// A crowded board from the middle of a game
+fn blocks() -> Vec<(Number, Position)> {
+    let rows = [[1, 2, 3, 1], [4, 1, 0, 2], [5, 0, 2, 2], [6, 3, 0, 1]];
+    let mut blocks = vec![];
+    for (y, row) in rows.iter().enumerate() {
+        for (x, exponent) in row.iter().enumerate() {
+            if *exponent != 0 {
+                blocks.push((Number::from_exponent(*exponent), Position::from(x, y)));
+            }
+        }
+    }
+    blocks
+}
+
+fn config(seed: u64) -> GameConfig {
+    GameConfig {
+        rng: RngConfig { seed: Some(seed) },
+        ..GameConfig::default()
+    }
+}
+
+fn single_move(c: &mut Criterion) {
+    let mut group = c.benchmark_group("single move");
+
+    let game = Game::from_blocks(config(0), blocks());
+    group.bench_function("position map", |b| {
+        b.iter_batched(
+            || game.clone(),
+            |mut game| {
+                for direction in Direction::ALL.iter() {
+                    black_box(game.apply(*direction));
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    let board = game.bitboard().unwrap();
+    group.bench_function("bitboard", |b| {
+        b.iter(|| {
+            for direction in Direction::ALL.iter() {
+                black_box(black_box(board).apply(*direction));
+            }
+        })
+    });
+
+    group.finish();
+}
+
+/// Plays whole games, always taking the first legal move in a fixed order
+fn full_game(c: &mut Criterion) {
+    let mut group = c.benchmark_group("full game");
+
+    group.bench_function("position map", |b| {
+        b.iter(|| {
+            let mut game = Game::new(config(7));
+            while let Some(direction) = Direction::ALL.iter().find(|d| game.can_move(**d)) {
+                if let MoveOutcome::Blocked = game.apply(*direction) {
+                    break;
+                }
+            }
+            black_box(game.score())
+        })
+    });
+
+    group.bench_function("bitboard", |b| {
+        b.iter(|| {
+            let mut rng = Pcg32::seed_from_u64(7);
+            let mut board = Bitboard::EMPTY.spawn(&mut rng).unwrap();
+            let mut score = 0;
+            while let Some(direction) = Direction::ALL.iter().find(|d| board.can_move(**d)) {
+                let (moved, points) = board.apply(*direction);
+                score += points;
+                board = moved.spawn(&mut rng).unwrap();
+            }
+            black_box(score)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, single_move, full_game);
+criterion_main!(benches);
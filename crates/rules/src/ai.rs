@@ -0,0 +1,488 @@
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::bitboard::{self, Bitboard};
+use crate::position_map::{Direction, Position, PositionMap};
+
+/// How far the search looks ahead
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchConfig {
+    /// Moves of the player looked ahead
+    pub depth: u32,
+    /// Searching deeper stops once this is spent, the deepest finished
+    /// search is used. A search one move deep always finishes.
+    pub time_budget: Option<Duration>,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            depth: 3,
+            time_budget: Some(Duration::from_millis(100)),
+        }
+    }
+}
+
+/// Expected value of the board after a move
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveScore {
+    pub direction: Direction,
+    pub score: f64,
+}
+
+/// A board the search can play on
+pub trait SearchBoard: Sized {
+    /// The board after a move, `None` when nothing moves
+    fn slide(&self, direction: Direction) -> Option<Self>;
+    fn empty_positions(&self) -> Vec<Position>;
+    fn with_block(&self, position: Position, exponent: u32) -> Self;
+    /// How promising the board is, never below the score of a lost board
+    fn heuristic(&self) -> f64;
+}
+
+/// Score of a board where no move is left
+pub const LOST: f64 = 0.0;
+
+const BASE: f64 = 200_000.0;
+const EMPTY_WEIGHT: f64 = 270.0;
+const MERGE_WEIGHT: f64 = 700.0;
+const MONOTONICITY_POWER: f64 = 4.0;
+const MONOTONICITY_WEIGHT: f64 = 47.0;
+const SMOOTHNESS_WEIGHT: f64 = 10.0;
+const SUM_POWER: f64 = 3.5;
+const SUM_WEIGHT: f64 = 11.0;
+const CORNER_WEIGHT: f64 = 1000.0;
+
+/// Spawns less likely than this are not searched any deeper
+const PROBABILITY_CUTOFF: f64 = 0.0001;
+const SPAWNS: [(u32, f64); 2] = [(1, 0.9), (2, 0.1)];
+
+/// Scores every legal move on the board, the 4x4 board is searched as a bitboard
+pub fn evaluate(board: &PositionMap, config: &SearchConfig) -> Vec<MoveScore> {
+    match Bitboard::from_position_map(board) {
+        Some(bitboard) => evaluate_board(&bitboard, config),
+        None => evaluate_board(&Grid::from_position_map(board), config),
+    }
+}
+
+pub fn best_move(board: &PositionMap, config: &SearchConfig) -> Option<Direction> {
+    best(&evaluate(board, config))
+}
+
+/// The move with the highest score
+pub fn best(scores: &[MoveScore]) -> Option<Direction> {
+    scores
+        .iter()
+        .max_by(|a, b| a.score.total_cmp(&b.score))
+        .map(|score| score.direction)
+}
+
+/// Expectimax search, deepening one move at a time until the depth or the
+/// time budget is reached.
+pub fn evaluate_board<B: SearchBoard>(board: &B, config: &SearchConfig) -> Vec<MoveScore> {
+    let deadline = config.time_budget.map(|budget| Instant::now() + budget);
+    let mut scores = Search::new(None)
+        .root(board, 1)
+        .expect("A search without deadline always finishes");
+
+    for depth in 2..=config.depth {
+        match Search::new(deadline).root(board, depth) {
+            Some(deeper) => scores = deeper,
+            None => break,
+        }
+    }
+    scores
+}
+
+struct Search {
+    deadline: Option<Instant>,
+    nodes: u32,
+}
+
+impl Search {
+    fn new(deadline: Option<Instant>) -> Self {
+        Self {
+            deadline: deadline,
+            nodes: 0,
+        }
+    }
+
+    fn root<B: SearchBoard>(&mut self, board: &B, depth: u32) -> Option<Vec<MoveScore>> {
+        let mut scores = vec![];
+        for direction in Direction::ALL.iter() {
+            if let Some(moved) = board.slide(*direction) {
+                scores.push(MoveScore {
+                    direction: *direction,
+                    score: self.chance(&moved, depth - 1, 1.0)?,
+                });
+            }
+        }
+        Some(scores)
+    }
+
+    /// Average over every block that may spawn, `None` once out of time
+    fn chance<B: SearchBoard>(&mut self, board: &B, depth: u32, probability: f64) -> Option<f64> {
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(1024) && self.deadline.is_some_and(|d| Instant::now() > d) {
+            return None;
+        }
+
+        let empty = board.empty_positions();
+        if depth == 0 || probability < PROBABILITY_CUTOFF || empty.is_empty() {
+            return Some(board.heuristic());
+        }
+
+        let mut total = 0.0;
+        for position in empty.iter() {
+            for (exponent, chance) in SPAWNS.iter() {
+                let spawned = board.with_block(*position, *exponent);
+                let probability = probability * chance / empty.len() as f64;
+                total += chance * self.max(&spawned, depth, probability)?;
+            }
+        }
+        Some(total / empty.len() as f64)
+    }
+
+    /// Best move for the player
+    fn max<B: SearchBoard>(&mut self, board: &B, depth: u32, probability: f64) -> Option<f64> {
+        let mut best = LOST;
+        for direction in Direction::ALL.iter() {
+            if let Some(moved) = board.slide(*direction) {
+                best = best.max(self.chance(&moved, depth - 1, probability)?);
+            }
+        }
+        Some(best)
+    }
+}
+
+/// Heuristic of one row or column: empty cells, possible merges, blocks
+/// ordered from one end to the other and small steps between neighbours.
+fn line_score(cells: &[u32]) -> f64 {
+    let empty = cells.iter().filter(|cell| **cell == 0).count() as f64;
+    let sum: f64 = cells
+        .iter()
+        .map(|cell| (*cell as f64).powf(SUM_POWER))
+        .sum();
+
+    let blocks: Vec<u32> = cells.iter().copied().filter(|cell| *cell != 0).collect();
+    let merges = blocks.windows(2).filter(|pair| pair[0] == pair[1]).count() as f64;
+    let smoothness: f64 = blocks
+        .windows(2)
+        .map(|pair| (pair[0] as f64 - pair[1] as f64).abs())
+        .sum();
+
+    let (mut decreasing, mut increasing) = (0.0, 0.0);
+    for pair in cells.windows(2) {
+        let first = (pair[0] as f64).powf(MONOTONICITY_POWER);
+        let second = (pair[1] as f64).powf(MONOTONICITY_POWER);
+        if pair[0] > pair[1] {
+            decreasing += first - second;
+        } else {
+            increasing += second - first;
+        }
+    }
+
+    EMPTY_WEIGHT * empty + MERGE_WEIGHT * merges
+        - MONOTONICITY_WEIGHT * f64::min(decreasing, increasing)
+        - SMOOTHNESS_WEIGHT * smoothness
+        - SUM_WEIGHT * sum
+}
+
+fn corner_score(max: u32, corners: [u32; 4]) -> f64 {
+    if max > 0 && corners.contains(&max) {
+        CORNER_WEIGHT * max as f64
+    } else {
+        0.0
+    }
+}
+
+fn row_scores() -> &'static [f64] {
+    static SCORES: OnceLock<Box<[f64]>> = OnceLock::new();
+    SCORES.get_or_init(|| {
+        (0..=u16::MAX)
+            .map(|row| line_score(&bitboard::unpack_row(row)))
+            .collect()
+    })
+}
+
+impl SearchBoard for Bitboard {
+    fn slide(&self, direction: Direction) -> Option<Self> {
+        let (moved, _) = self.apply(direction);
+        if moved == *self {
+            None
+        } else {
+            Some(moved)
+        }
+    }
+
+    fn empty_positions(&self) -> Vec<Position> {
+        Bitboard::empty_positions(self).collect()
+    }
+
+    fn with_block(&self, position: Position, exponent: u32) -> Self {
+        let mut board = *self;
+        board.set(position.x, position.y, exponent);
+        board
+    }
+
+    fn heuristic(&self) -> f64 {
+        let scores = row_scores();
+        let lines = |bits: u64| -> f64 {
+            (0..4)
+                .map(|y| scores[(bits >> (16 * y)) as u16 as usize])
+                .sum()
+        };
+        let corners = [
+            self.get(0, 0),
+            self.get(3, 0),
+            self.get(0, 3),
+            self.get(3, 3),
+        ];
+
+        let score = BASE
+            + lines(self.bits())
+            + lines(bitboard::transpose(self.bits()))
+            + corner_score(self.max_exponent(), corners);
+        score.max(LOST)
+    }
+}
+
+/// Board of any size as exponents in reading order, 0 being an empty cell
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    width: i32,
+    height: i32,
+    cells: Vec<u32>,
+}
+
+impl Grid {
+    pub fn from_position_map(map: &PositionMap) -> Self {
+        let mut cells = vec![0; (map.width() * map.height()) as usize];
+        for (_, number, position) in map.blocks() {
+            cells[(position.y * map.width() + position.x) as usize] = number.exponent();
+        }
+
+        Self {
+            width: map.width(),
+            height: map.height(),
+            cells: cells,
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    /// Cells of every row or column, listed in the direction of the move
+    fn lines(&self, direction: Direction) -> Vec<Vec<usize>> {
+        let (width, height) = (self.width, self.height);
+        match direction {
+            Direction::LEFT => (0..height)
+                .map(|y| (0..width).map(|x| self.index(x, y)).collect())
+                .collect(),
+            Direction::RIGHT => (0..height)
+                .map(|y| (0..width).rev().map(|x| self.index(x, y)).collect())
+                .collect(),
+            Direction::TOP => (0..width)
+                .map(|x| (0..height).map(|y| self.index(x, y)).collect())
+                .collect(),
+            Direction::BOTTOM => (0..width)
+                .map(|x| (0..height).rev().map(|y| self.index(x, y)).collect())
+                .collect(),
+        }
+    }
+}
+
+impl SearchBoard for Grid {
+    fn slide(&self, direction: Direction) -> Option<Self> {
+        let mut moved = self.clone();
+        for line in self.lines(direction) {
+            let blocks: Vec<u32> = line
+                .iter()
+                .map(|index| self.cells[*index])
+                .filter(|cell| *cell != 0)
+                .collect();
+
+            let mut merged = vec![];
+            let mut i = 0;
+            while i < blocks.len() {
+                if i + 1 < blocks.len() && blocks[i] == blocks[i + 1] {
+                    merged.push(blocks[i] + 1);
+                    i += 2;
+                } else {
+                    merged.push(blocks[i]);
+                    i += 1;
+                }
+            }
+
+            for (i, index) in line.iter().enumerate() {
+                moved.cells[*index] = merged.get(i).copied().unwrap_or(0);
+            }
+        }
+
+        if moved == *self {
+            None
+        } else {
+            Some(moved)
+        }
+    }
+
+    fn empty_positions(&self) -> Vec<Position> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| Position { x: x, y: y }))
+            .filter(|position| self.cells[self.index(position.x, position.y)] == 0)
+            .collect()
+    }
+
+    fn with_block(&self, position: Position, exponent: u32) -> Self {
+        let mut grid = self.clone();
+        let index = self.index(position.x, position.y);
+        grid.cells[index] = exponent;
+        grid
+    }
+
+    fn heuristic(&self) -> f64 {
+        let lines: f64 = [Direction::LEFT, Direction::TOP]
+            .iter()
+            .flat_map(|direction| self.lines(*direction))
+            .map(|line| {
+                let cells: Vec<u32> = line.iter().map(|index| self.cells[*index]).collect();
+                line_score(&cells)
+            })
+            .sum();
+        let (right, bottom) = (self.width - 1, self.height - 1);
+        let corners = [
+            self.cells[self.index(0, 0)],
+            self.cells[self.index(right, 0)],
+            self.cells[self.index(0, bottom)],
+            self.cells[self.index(right, bottom)],
+        ];
+        let max = self.cells.iter().copied().max().unwrap_or(0);
+
+        (BASE + lines + corner_score(max, corners)).max(LOST)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BoardConfig, Game, GameConfig, MoveOutcome};
+    use crate::number::Number;
+    use crate::rng::RngConfig;
+
+    fn map(rows: &[&[u32]]) -> PositionMap {
+        let mut map = PositionMap::new(rows[0].len() as i32, rows.len() as i32);
+        let mut id = 0;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, exponent) in row.iter().enumerate() {
+                if *exponent != 0 {
+                    map.add_block(id, Number::from_exponent(*exponent));
+                    map.set(x as i32, y as i32, Some(id));
+                    id += 1;
+                }
+            }
+        }
+        map
+    }
+
+    fn exhaustive(depth: u32) -> SearchConfig {
+        SearchConfig {
+            depth: depth,
+            time_budget: None,
+        }
+    }
+
+    #[test]
+    fn only_legal_moves_are_scored() {
+        let board = map(&[&[1, 0, 0, 0], &[2, 0, 0, 0], &[0; 4], &[0; 4]]);
+
+        let directions: Vec<Direction> = evaluate(&board, &exhaustive(2))
+            .iter()
+            .map(|score| score.direction)
+            .collect();
+
+        assert_eq!(directions, vec![Direction::RIGHT, Direction::BOTTOM]);
+    }
+
+    #[test]
+    fn lost_board_has_no_best_move() {
+        let board = map(&[&[1, 2], &[2, 1]]);
+
+        assert!(evaluate(&board, &exhaustive(3)).is_empty());
+        assert_eq!(best_move(&board, &exhaustive(3)), None);
+    }
+
+    #[test]
+    fn bitboard_and_grid_agree() {
+        let board = map(&[&[1, 2, 3, 4], &[0, 1, 0, 2], &[1, 0, 0, 0], &[0, 0, 0, 1]]);
+        let bitboard = Bitboard::from_position_map(&board).unwrap();
+        let grid = Grid::from_position_map(&board);
+
+        let from_bitboard = evaluate_board(&bitboard, &exhaustive(2));
+        let from_grid = evaluate_board(&grid, &exhaustive(2));
+
+        assert_eq!(from_bitboard.len(), from_grid.len());
+        for (a, b) in from_bitboard.iter().zip(from_grid.iter()) {
+            assert_eq!(a.direction, b.direction);
+            assert!((a.score - b.score).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn ordered_rows_score_higher() {
+        assert!(line_score(&[4, 3, 2, 1]) > line_score(&[3, 1, 4, 2]));
+        assert!(line_score(&[2, 2, 0, 0]) > line_score(&[2, 3, 0, 0]));
+    }
+
+    #[test]
+    fn time_budget_stops_deep_searches() {
+        let board = map(&[&[1, 0, 0, 0], &[0; 4], &[0, 0, 2, 0], &[0; 4]]);
+        let config = SearchConfig {
+            depth: 20,
+            time_budget: Some(Duration::from_millis(20)),
+        };
+
+        let start = Instant::now();
+        let scores = evaluate(&board, &config);
+
+        assert_eq!(scores.len(), 4);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    fn play(board: BoardConfig) -> Game {
+        let mut game = Game::new(GameConfig {
+            board: board,
+            rng: RngConfig { seed: Some(3) },
+            ..GameConfig::default()
+        });
+        while let Some(direction) = best_move(game.board(), &exhaustive(2)) {
+            assert!(matches!(game.apply(direction), MoveOutcome::Moved(_)));
+        }
+        game
+    }
+
+    fn max_exponent(game: &Game) -> u32 {
+        game.board()
+            .blocks()
+            .iter()
+            .map(|(_, number, _)| number.exponent())
+            .max()
+            .unwrap()
+    }
+
+    #[test]
+    fn plays_a_game_to_a_big_block() {
+        let game = play(BoardConfig::default());
+
+        assert!(game.is_game_over());
+        assert!(max_exponent(&game) >= 10);
+    }
+
+    #[test]
+    fn plays_on_other_board_sizes() {
+        let game = play(BoardConfig::new(3, 3).unwrap());
+
+        assert!(game.is_game_over());
+        assert!(max_exponent(&game) >= 7);
+    }
+}
@@ -0,0 +1,392 @@
+use std::fmt;
+use std::sync::OnceLock;
+
+use rand::Rng;
+
+use crate::number::Number;
+use crate::position_map::{Direction, Position, PositionMap};
+
+/// A 4x4 board packed into 64 bits, for simulating moves fast when the
+/// identity of each block doesn't matter.
+///
+/// Every cell takes 4 bits holding the exponent of its block, 0 being an
+/// empty cell. Cell (x, y) starts at bit `4 * (4 * y + x)`, so each row is
+/// 16 bits. Blocks bigger than 2^15 can't be stored, and two 2^15 blocks
+/// don't merge.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Bitboard(u64);
+
+pub const SIZE: i32 = 4;
+pub const MAX_EXPONENT: u32 = 15;
+
+const ROWS: usize = 1 << 16;
+
+/// Results of moving every possible row, indexed by the row
+struct Tables {
+    left: Box<[u16]>,
+    right: Box<[u16]>,
+    points: Box<[u64]>,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut left = vec![0u16; ROWS];
+        let mut right = vec![0u16; ROWS];
+        let mut points = vec![0u64; ROWS];
+
+        for row in 0..ROWS {
+            let cells = unpack_row(row as u16);
+            let (moved, gained) = slide_row(cells);
+            left[row] = pack_row(moved);
+            points[row] = gained;
+
+            let mut reversed = cells;
+            reversed.reverse();
+            let (mut moved, _) = slide_row(reversed);
+            moved.reverse();
+            right[row] = pack_row(moved);
+        }
+
+        Tables {
+            left: left.into_boxed_slice(),
+            right: right.into_boxed_slice(),
+            points: points.into_boxed_slice(),
+        }
+    })
+}
+
+pub(crate) fn unpack_row(row: u16) -> [u32; 4] {
+    [0, 1, 2, 3].map(|i| (row >> (4 * i)) as u32 & 0xf)
+}
+
+fn pack_row(cells: [u32; 4]) -> u16 {
+    cells
+        .iter()
+        .enumerate()
+        .fold(0, |row, (i, cell)| row | (*cell as u16) << (4 * i))
+}
+
+/// Slides a row towards its first cell, merging each pair of equal blocks once
+fn slide_row(cells: [u32; 4]) -> ([u32; 4], u64) {
+    let mut result = [0; 4];
+    let mut points = 0;
+    let mut target = 0;
+    let mut pending: Option<u32> = None;
+
+    for cell in cells.iter().copied().filter(|cell| *cell != 0) {
+        match pending {
+            Some(previous) if previous == cell && cell < MAX_EXPONENT => {
+                result[target] = cell + 1;
+                points += 1u64 << (cell + 1);
+                target += 1;
+                pending = None;
+            }
+            Some(previous) => {
+                result[target] = previous;
+                target += 1;
+                pending = Some(cell);
+            }
+            None => pending = Some(cell),
+        }
+    }
+    if let Some(previous) = pending {
+        result[target] = previous;
+    }
+
+    (result, points)
+}
+
+/// Swaps rows and columns
+pub(crate) fn transpose(board: u64) -> u64 {
+    let a1 = board & 0xF0F0_0F0F_F0F0_0F0F;
+    let a2 = board & 0x0000_F0F0_0000_F0F0;
+    let a3 = board & 0x0F0F_0000_0F0F_0000;
+    let a = a1 | (a2 << 12) | (a3 >> 12);
+    let b1 = a & 0xFF00_FF00_00FF_00FF;
+    let b2 = a & 0x00FF_00FF_0000_0000;
+    let b3 = a & 0x0000_0000_FF00_FF00;
+    b1 | (b2 >> 24) | (b3 << 24)
+}
+
+fn move_rows(board: u64, table: &[u16]) -> (u64, u64) {
+    let points = &tables().points;
+    (0..4).fold((0, 0), |(moved, gained), y| {
+        let row = (board >> (16 * y)) as u16 as usize;
+        (
+            moved | (table[row] as u64) << (16 * y),
+            gained + points[row],
+        )
+    })
+}
+
+impl Bitboard {
+    pub const EMPTY: Bitboard = Bitboard(0);
+
+    pub fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Packs a 4x4 map, `None` when the board is another size or a block is too big
+    pub fn from_position_map(map: &PositionMap) -> Option<Self> {
+        if map.width() != SIZE || map.height() != SIZE {
+            return None;
+        }
+
+        let mut board = Self::EMPTY;
+        for (_, number, position) in map.blocks() {
+            if number.exponent() > MAX_EXPONENT {
+                return None;
+            }
+            board.set(position.x, position.y, number.exponent());
+        }
+        Some(board)
+    }
+
+    /// Unpacks into a map, giving the blocks ids in reading order
+    pub fn to_position_map(&self) -> PositionMap {
+        let mut map = PositionMap::new(SIZE, SIZE);
+        let mut id = 0;
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                let exponent = self.get(x, y);
+                if exponent != 0 {
+                    map.add_block(id, Number::from_exponent(exponent));
+                    map.set(x, y, Some(id));
+                    id += 1;
+                }
+            }
+        }
+        map
+    }
+
+    /// Exponent of the block at a cell, 0 when it is empty
+    pub fn get(&self, x: i32, y: i32) -> u32 {
+        (self.0 >> Self::shift(x, y)) as u32 & 0xf
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, exponent: u32) {
+        assert!(exponent <= MAX_EXPONENT, "Block 2^{} is too big", exponent);
+        let shift = Self::shift(x, y);
+        self.0 = (self.0 & !(0xf << shift)) | (exponent as u64) << shift;
+    }
+
+    fn shift(x: i32, y: i32) -> u32 {
+        assert!((0..SIZE).contains(&x) && (0..SIZE).contains(&y));
+        (4 * (SIZE * y + x)) as u32
+    }
+
+    /// The board after a move and the points scored by its merges
+    pub fn apply(&self, direction: Direction) -> (Bitboard, u64) {
+        let tables = tables();
+        let (board, points) = match direction {
+            Direction::LEFT => move_rows(self.0, &tables.left),
+            Direction::RIGHT => move_rows(self.0, &tables.right),
+            Direction::TOP => {
+                let (board, points) = move_rows(transpose(self.0), &tables.left);
+                (transpose(board), points)
+            }
+            Direction::BOTTOM => {
+                let (board, points) = move_rows(transpose(self.0), &tables.right);
+                (transpose(board), points)
+            }
+        };
+        (Bitboard(board), points)
+    }
+
+    pub fn can_move(&self, direction: Direction) -> bool {
+        self.apply(direction).0 != *self
+    }
+
+    pub fn has_available_moves(&self) -> bool {
+        Direction::ALL
+            .iter()
+            .any(|direction| self.can_move(*direction))
+    }
+
+    pub fn empty_cells(&self) -> u32 {
+        (0..16)
+            .filter(|cell| (self.0 >> (4 * cell)) & 0xf == 0)
+            .count() as u32
+    }
+
+    /// Every empty cell, in reading order
+    pub fn empty_positions(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..SIZE)
+            .flat_map(|y| (0..SIZE).map(move |x| Position { x: x, y: y }))
+            .filter(move |position| self.get(position.x, position.y) == 0)
+    }
+
+    pub fn max_exponent(&self) -> u32 {
+        (0..16)
+            .map(|cell| (self.0 >> (4 * cell)) as u32 & 0xf)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Adds a 2, or a 4 one time in ten, on a random empty cell. Uses the
+    /// random numbers in the same way as `Game`, so a seed gives the same
+    /// spawns on both.
+    pub fn spawn(&self, rng: &mut impl Rng) -> Option<Bitboard> {
+        let random: f32 = rng.gen_range(0.0..1.0);
+        let exponent = if random > 0.9 { 2 } else { 1 };
+
+        let free = self.empty_cells();
+        if free == 0 {
+            return None;
+        }
+
+        // Same order as `PositionMap::get_random_free_position`, column by column
+        let chosen = rng.gen_range(0..free as i32) as usize;
+        let position = (0..SIZE)
+            .flat_map(|x| (0..SIZE).map(move |y| Position { x: x, y: y }))
+            .filter(|position| self.get(position.x, position.y) == 0)
+            .nth(chosen)?;
+
+        let mut board = *self;
+        board.set(position.x, position.y, exponent);
+        Some(board)
+    }
+}
+
+impl fmt::Debug for Bitboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows: Vec<[u32; 4]> = (0..SIZE)
+            .map(|y| [0, 1, 2, 3].map(|x| self.get(x, y)))
+            .collect();
+        f.debug_tuple("Bitboard").field(&rows).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Game, GameConfig, MoveOutcome};
+    use crate::rng::{GameRng, RngConfig};
+    use proptest::prelude::*;
+
+    fn board(rows: [[u32; 4]; 4]) -> Bitboard {
+        let mut board = Bitboard::EMPTY;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, exponent) in row.iter().enumerate() {
+                board.set(x as i32, y as i32, *exponent);
+            }
+        }
+        board
+    }
+
+    fn game_from(board: &Bitboard, config: GameConfig) -> Game {
+        let blocks = board
+            .to_position_map()
+            .blocks()
+            .into_iter()
+            .map(|(_, number, position)| (number, position));
+        Game::from_blocks(config, blocks)
+    }
+
+    fn board_strategy() -> impl Strategy<Value = Bitboard> {
+        prop::collection::vec(prop::option::weighted(0.7, 1u32..6), 16).prop_map(|cells| {
+            let mut board = Bitboard::EMPTY;
+            for (i, cell) in cells.into_iter().enumerate() {
+                board.set(i as i32 % 4, i as i32 / 4, cell.unwrap_or(0));
+            }
+            board
+        })
+    }
+
+    #[test]
+    fn slides_and_merges_each_pair_once() {
+        let start = board([[1, 1, 1, 1], [0, 2, 0, 2], [3, 0, 0, 0], [1, 2, 1, 2]]);
+
+        let (moved, points) = start.apply(Direction::LEFT);
+
+        assert_eq!(
+            moved,
+            board([[2, 2, 0, 0], [3, 0, 0, 0], [3, 0, 0, 0], [1, 2, 1, 2]])
+        );
+        assert_eq!(points, 4 + 4 + 8);
+    }
+
+    #[test]
+    fn moves_columns_up_and_down() {
+        let start = board([[1, 0, 0, 0], [1, 0, 0, 3], [0, 0, 0, 0], [2, 0, 0, 0]]);
+
+        assert_eq!(
+            start.apply(Direction::TOP).0,
+            board([[2, 0, 0, 3], [2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])
+        );
+        assert_eq!(
+            start.apply(Direction::BOTTOM).0,
+            board([[0, 0, 0, 0], [0, 0, 0, 0], [2, 0, 0, 0], [2, 0, 0, 3]])
+        );
+    }
+
+    #[test]
+    fn biggest_blocks_do_not_merge() {
+        let start = board([[15, 15, 0, 0], [0; 4], [0; 4], [0; 4]]);
+
+        assert!(!start.can_move(Direction::LEFT));
+        assert!(start.can_move(Direction::RIGHT));
+    }
+
+    #[test]
+    fn other_board_sizes_are_not_packed() {
+        assert!(Bitboard::from_position_map(&PositionMap::new(5, 4)).is_none());
+    }
+
+    #[test]
+    fn spawns_like_the_game() {
+        let start = board([[1, 0, 0, 0], [0; 4], [0, 2, 0, 0], [0; 4]]);
+        let config = GameConfig {
+            rng: RngConfig { seed: Some(9) },
+            ..GameConfig::default()
+        };
+        let mut game = game_from(&start, config);
+        let mut rng = GameRng::new(9);
+
+        let mut board = start;
+        for direction in [Direction::RIGHT, Direction::BOTTOM, Direction::LEFT].iter() {
+            if let MoveOutcome::Moved(_) = game.apply(*direction) {
+                board = board.apply(*direction).0.spawn(&mut rng).unwrap();
+            }
+        }
+
+        assert_eq!(Bitboard::from_position_map(game.board()), Some(board));
+    }
+
+    proptest! {
+        #[test]
+        fn converts_to_and_from_position_map(board in board_strategy()) {
+            prop_assert_eq!(Bitboard::from_position_map(&board.to_position_map()), Some(board));
+        }
+
+        #[test]
+        fn transpose_swaps_rows_and_columns(board in board_strategy()) {
+            let transposed = Bitboard(transpose(board.0));
+            for x in 0..SIZE {
+                for y in 0..SIZE {
+                    prop_assert_eq!(transposed.get(y, x), board.get(x, y));
+                }
+            }
+        }
+
+        #[test]
+        fn moves_match_the_game(board in board_strategy(), index in 0usize..4) {
+            let direction = Direction::ALL[index];
+            let mut game = game_from(&board, GameConfig::default());
+            let (moved, points) = board.apply(direction);
+
+            match game.move_blocks(direction) {
+                Some(turn) => {
+                    prop_assert_eq!(Bitboard::from_position_map(game.board()), Some(moved));
+                    prop_assert_eq!(turn.points, points);
+                }
+                None => prop_assert_eq!(moved, board),
+            }
+        }
+    }
+}
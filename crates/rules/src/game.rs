@@ -0,0 +1,842 @@
+use std::str::FromStr;
+
+use rand::Rng;
+
+use crate::bitboard::Bitboard;
+use crate::history::{History, Snapshot, UndoConfig};
+use crate::number::Number;
+use crate::position_map::{Direction, Id, Position, PositionMap};
+use crate::rng::{GameRng, RngConfig};
+
+/// Dimensions of the board, chosen at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardConfig {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl BoardConfig {
+    pub const MIN_SIZE: i32 = 2;
+    pub const MAX_SIZE: i32 = 12;
+
+    pub fn new(width: i32, height: i32) -> Result<Self, String> {
+        let range = Self::MIN_SIZE..=Self::MAX_SIZE;
+        if !range.contains(&width) || !range.contains(&height) {
+            return Err(format!(
+                "Board dimensions must be between {} and {}, got {}x{}",
+                Self::MIN_SIZE,
+                Self::MAX_SIZE,
+                width,
+                height
+            ));
+        }
+
+        Ok(Self { width, height })
+    }
+}
+
+/// Reads a size such as `5x4`, width first
+impl FromStr for BoardConfig {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid board size {}, expected WIDTHxHEIGHT", value);
+        let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+        let width = width.parse::<i32>().map_err(|_| invalid())?;
+        let height = height.parse::<i32>().map_err(|_| invalid())?;
+
+        Self::new(width, height)
+    }
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        Self {
+            width: 4,
+            height: 4,
+        }
+    }
+}
+
+/// Block that wins the game once it is first made by a merge.
+#[derive(Debug, Clone, Copy)]
+pub struct WinConfig {
+    pub target: Number,
+}
+
+impl Default for WinConfig {
+    fn default() -> Self {
+        Self {
+            target: Number::from_exponent(11),
+        }
+    }
+}
+
+/// Everything a game is set up with
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GameConfig {
+    pub board: BoardConfig,
+    pub undo: UndoConfig,
+    pub rng: RngConfig,
+    pub win: WinConfig,
+}
+
+/// Two blocks that were merged into a new one
+#[derive(Debug, Clone, Copy)]
+pub struct Merge {
+    pub merged: (Id, Id),
+    pub id: Id,
+    pub number: Number,
+    pub position: Position,
+}
+
+/// What happened to the board during one move
+#[derive(Debug, Clone, Default)]
+pub struct Turn {
+    /// Where every block slid to, merged blocks included
+    pub moves: Vec<(Id, Position)>,
+    pub merges: Vec<Merge>,
+    /// The random block added after the move
+    pub spawned: Option<(Id, Number, Position)>,
+    /// Value of every block made by a merge
+    pub points: u64,
+    /// The win target was made for the first time
+    pub won: bool,
+    /// No move is left after this one
+    pub game_over: bool,
+}
+
+#[derive(Debug)]
+pub enum MoveOutcome {
+    /// Nothing can move that way, the board is unchanged
+    Blocked,
+    Moved(Turn),
+}
+
+#[derive(Debug, Clone)]
+pub struct Game {
+    config: GameConfig,
+    position_map: PositionMap,
+    current_id: Id,
+    score: u64,
+    history: History,
+    rng: GameRng,
+    is_game_over: bool,
+    has_won: bool,
+}
+
+impl Game {
+    /// Starts a game with a single random block
+    pub fn new(config: GameConfig) -> Self {
+        let mut game = Self::from_blocks(config, []);
+        game.spawn_block();
+        game
+    }
+
+    /// Starts a game from the given blocks, panics if one is off the board
+    pub fn from_blocks(
+        config: GameConfig,
+        blocks: impl IntoIterator<Item = (Number, Position)>,
+    ) -> Self {
+        let mut game = Self {
+            config: config,
+            position_map: PositionMap::new(config.board.width, config.board.height),
+            current_id: 0,
+            score: 0,
+            history: History::new(config.undo),
+            rng: GameRng::from_config(&config.rng),
+            is_game_over: false,
+            has_won: false,
+        };
+        for (number, position) in blocks {
+            game.add_block(number, position);
+        }
+        game
+    }
+
+    /// Starts over with a single random block. The seed of the next game
+    /// follows from the current one.
+    pub fn restart(&mut self) {
+        self.rng.next_game();
+        self.position_map = PositionMap::new(self.config.board.width, self.config.board.height);
+        self.current_id = 0;
+        self.score = 0;
+        self.history.clear();
+        self.is_game_over = false;
+        self.has_won = false;
+        self.spawn_block();
+    }
+
+    /// Slides every block towards `direction`, merges equal neighbours
+    /// and adds a random block.
+    pub fn apply(&mut self, direction: Direction) -> MoveOutcome {
+        match self.move_blocks(direction) {
+            Some(mut turn) => {
+                turn.spawned = self.spawn_block();
+                turn.game_over = !self.position_map.has_available_moves();
+                self.is_game_over = turn.game_over;
+                MoveOutcome::Moved(turn)
+            }
+            None => MoveOutcome::Blocked,
+        }
+    }
+
+    /// Restores the board from before the last move
+    pub fn undo(&mut self) -> bool {
+        if let Some(snapshot) = self.history.pop() {
+            self.position_map = snapshot.position_map;
+            self.score = snapshot.score;
+            self.current_id = snapshot.current_id;
+            self.has_won = snapshot.has_won;
+            self.is_game_over = false;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn board(&self) -> &PositionMap {
+        &self.position_map
+    }
+
+    /// The board packed for fast simulation, when it fits in one
+    pub fn bitboard(&self) -> Option<Bitboard> {
+        Bitboard::from_position_map(&self.position_map)
+    }
+
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    pub fn score(&self) -> u64 {
+        self.score
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.rng.seed()
+    }
+
+    pub fn can_move(&self, direction: Direction) -> bool {
+        !self.is_game_over && self.position_map.can_move(direction)
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.is_game_over
+    }
+
+    /// The win target was reached in this game
+    pub fn has_won(&self) -> bool {
+        self.has_won
+    }
+
+    pub fn undos_left(&self) -> Option<usize> {
+        self.history.undos_left()
+    }
+
+    pub(crate) fn current_id(&self) -> Id {
+        self.current_id
+    }
+
+    pub(crate) fn rng(&self) -> &GameRng {
+        &self.rng
+    }
+
+    /// Takes over a board that was checked by the caller, history is dropped
+    pub(crate) fn replace(
+        &mut self,
+        position_map: PositionMap,
+        current_id: Id,
+        score: u64,
+        is_game_over: bool,
+        has_won: bool,
+        rng: GameRng,
+    ) {
+        self.position_map = position_map;
+        self.current_id = current_id;
+        self.score = score;
+        self.history.clear();
+        self.is_game_over = is_game_over;
+        self.has_won = has_won;
+        self.rng = rng;
+    }
+
+    fn add_block(&mut self, number: Number, position: Position) -> Id {
+        let id = self.current_id;
+        self.current_id += 1;
+
+        self.position_map.add_block(id, number);
+        self.position_map.set(position.x, position.y, Some(id));
+        id
+    }
+
+    fn spawn_block(&mut self) -> Option<(Id, Number, Position)> {
+        let random: f32 = self.rng.gen_range(0.0..1.0);
+        let number = if random > 0.9 {
+            Number::MIN.next()
+        } else {
+            Number::MIN
+        };
+
+        let position = self.position_map.get_random_free_position(&mut self.rng)?;
+        let id = self.add_block(number, position);
+        Some((id, number, position))
+    }
+
+    /// The move and its merges, without the random block
+    pub(crate) fn move_blocks(&mut self, direction: Direction) -> Option<Turn> {
+        if !self.can_move(direction) {
+            return None;
+        }
+
+        self.history.push(Snapshot {
+            position_map: self.position_map.clone(),
+            score: self.score,
+            current_id: self.current_id,
+            has_won: self.has_won,
+        });
+
+        let mut moves: Vec<(Id, Position)> = vec![];
+        let mut merges: Vec<(Id, Id, Position)> = vec![];
+        self.position_map = self.calculate_new_map(direction, &mut moves, &mut merges);
+
+        let mut turn = Turn::default();
+        for (id1, id2, position) in merges.into_iter() {
+            moves.push((id1, position));
+            moves.push((id2, position));
+            turn.merges.push(self.merge_blocks(id1, id2, position));
+        }
+        turn.moves = moves;
+
+        for merge in turn.merges.iter() {
+            turn.points = turn.points.saturating_add(merge.number.value());
+            if merge.number == self.config.win.target && !self.has_won {
+                self.has_won = true;
+                turn.won = true;
+            }
+        }
+        self.score = self.score.saturating_add(turn.points);
+
+        Some(turn)
+    }
+
+    /// Replaces two blocks with one of the next number
+    fn merge_blocks(&mut self, id1: Id, id2: Id, position: Position) -> Merge {
+        let number = self
+            .position_map
+            .get_number_with_id(id1)
+            .unwrap_or_else(|| panic!("Attempted to retrieve id {} but was not found", id1))
+            .next();
+        self.position_map.delete_block(id1);
+        self.position_map.delete_block(id2);
+
+        let id = self.add_block(number, position);
+        Merge {
+            merged: (id1, id2),
+            id: id,
+            number: number,
+            position: position,
+        }
+    }
+
+    fn calculate_new_map(
+        &mut self,
+        direction: Direction,
+        moves: &mut Vec<(Id, Position)>,
+        merges: &mut Vec<(Id, Id, Position)>,
+    ) -> PositionMap {
+        let mut new_map = self.position_map.new_with_existing_blocks();
+        let (start_index, lines) = match direction {
+            Direction::LEFT => (0, self.position_map.height()),
+            Direction::RIGHT => (self.position_map.width() - 1, self.position_map.height()),
+            Direction::TOP => (0, self.position_map.width()),
+            Direction::BOTTOM => (self.position_map.height() - 1, self.position_map.width()),
+        };
+
+        #[allow(unused_assignments)]
+        let mut column_row = start_index;
+
+        for line in 0..lines {
+            let mut cur_pos = self
+                .position_map
+                .get_not_empty_position_from(direction, line);
+            column_row = start_index;
+
+            while let Some(pos) = cur_pos {
+                let new_pos = new_position(line, &mut column_row, direction);
+                // The position was found by looking for a block, so there is one
+                let current_id = self
+                    .position_map
+                    .get(pos.x, pos.y)
+                    .expect("Expected a block at a non-empty position");
+                self.position_map.set(pos.x, pos.y, None);
+
+                let next_pos = self
+                    .position_map
+                    .get_not_empty_position_from(direction, line);
+                let next = next_pos.and_then(|p| self.position_map.get(p.x, p.y).map(|id| (p, id)));
+
+                let current_number = self.position_map.get_number_with_id(current_id);
+                let next_number = next.and_then(|(_, id)| self.position_map.get_number_with_id(id));
+
+                match next {
+                    Some((next_pos, next_id)) if current_number == next_number => {
+                        // merge these blocks, the next block is consumed so it
+                        // can't take part in another merge on this line
+                        self.position_map.set(next_pos.x, next_pos.y, None);
+                        new_map.set(new_pos.x, new_pos.y, Some(current_id));
+                        merges.push((current_id, next_id, new_pos));
+                    }
+                    _ => {
+                        new_map.set(new_pos.x, new_pos.y, Some(current_id));
+                        moves.push((current_id, new_pos))
+                    }
+                }
+
+                cur_pos = self
+                    .position_map
+                    .get_not_empty_position_from(direction, line);
+            }
+        }
+
+        new_map
+    }
+}
+
+fn new_position(line: i32, column_row: &mut i32, direction: Direction) -> Position {
+    let tmp = *column_row;
+    match direction {
+        Direction::LEFT => {
+            *column_row += 1;
+            Position { x: tmp, y: line }
+        }
+        Direction::RIGHT => {
+            *column_row -= 1;
+            Position { x: tmp, y: line }
+        }
+        Direction::TOP => {
+            *column_row += 1;
+            Position { x: line, y: tmp }
+        }
+        Direction::BOTTOM => {
+            *column_row -= 1;
+            Position { x: line, y: tmp }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn game_with(blocks: &[(Number, i32, i32)]) -> Game {
+        game_with_config(GameConfig::default(), blocks)
+    }
+
+    fn game_with_config(config: GameConfig, blocks: &[(Number, i32, i32)]) -> Game {
+        Game::from_blocks(
+            config,
+            blocks
+                .iter()
+                .map(|(number, x, y)| (*number, Position { x: *x, y: *y })),
+        )
+    }
+
+    /// Moves without the random block so boards can be checked exactly
+    fn move_and_merge(game: &mut Game, direction: Direction) -> Turn {
+        game.move_blocks(direction)
+            .expect("Expected the move to succeed")
+    }
+
+    fn number_at(game: &Game, x: i32, y: i32) -> Option<Number> {
+        game.board()
+            .get(x, y)
+            .and_then(|id| game.board().get_number_with_id(id))
+    }
+
+    #[test]
+    fn new_game_starts_with_one_block_and_zero_score() {
+        let game = Game::new(GameConfig::default());
+
+        assert_eq!(game.score(), 0);
+        assert_eq!(game.board().blocks().len(), 1);
+    }
+
+    #[test]
+    fn merge_adds_value_of_merged_block() {
+        let mut game = game_with(&[
+            (Number::from_exponent(1), 0, 0),
+            (Number::from_exponent(1), 1, 0),
+        ]);
+
+        let turn = move_and_merge(&mut game, Direction::LEFT);
+
+        assert_eq!(game.score(), 4);
+        assert_eq!(turn.points, 4);
+        assert_eq!(turn.merges.len(), 1);
+        assert_eq!(turn.merges[0].merged, (0, 1));
+        assert_eq!(number_at(&game, 0, 0), Some(Number::from_exponent(2)));
+    }
+
+    #[test]
+    fn multiple_merges_in_one_move_are_summed() {
+        let mut game = game_with(&[
+            (Number::from_exponent(1), 0, 0),
+            (Number::from_exponent(1), 1, 0),
+            (Number::from_exponent(3), 2, 0),
+            (Number::from_exponent(3), 3, 0),
+            (Number::from_exponent(2), 0, 1),
+            (Number::from_exponent(2), 3, 1),
+        ]);
+
+        move_and_merge(&mut game, Direction::LEFT);
+
+        assert_eq!(game.score(), 4 + 16 + 8);
+        assert_eq!(number_at(&game, 0, 0), Some(Number::from_exponent(2)));
+        assert_eq!(number_at(&game, 1, 0), Some(Number::from_exponent(4)));
+        assert_eq!(number_at(&game, 0, 1), Some(Number::from_exponent(3)));
+    }
+
+    #[test]
+    fn move_without_merge_does_not_score() {
+        let mut game = game_with(&[
+            (Number::from_exponent(1), 3, 0),
+            (Number::from_exponent(2), 3, 1),
+        ]);
+
+        let turn = move_and_merge(&mut game, Direction::LEFT);
+
+        assert_eq!(game.score(), 0);
+        assert!(turn.merges.is_empty());
+        assert_eq!(turn.moves.len(), 2);
+    }
+
+    #[test]
+    fn block_only_merges_once_per_move() {
+        let mut game = game_with(&[
+            (Number::from_exponent(1), 0, 0),
+            (Number::from_exponent(1), 1, 0),
+            (Number::from_exponent(1), 2, 0),
+        ]);
+
+        move_and_merge(&mut game, Direction::LEFT);
+
+        assert_eq!(game.score(), 4);
+        assert_eq!(number_at(&game, 0, 0), Some(Number::from_exponent(2)));
+        assert_eq!(number_at(&game, 1, 0), Some(Number::from_exponent(1)));
+        assert_eq!(number_at(&game, 2, 0), None);
+    }
+
+    #[test]
+    fn merged_block_is_not_overwritten_by_following_block() {
+        let mut game = game_with(&[
+            (Number::from_exponent(1), 1, 0),
+            (Number::from_exponent(1), 2, 0),
+            (Number::from_exponent(2), 3, 0),
+        ]);
+
+        move_and_merge(&mut game, Direction::LEFT);
+
+        assert_eq!(game.score(), 4);
+        assert_eq!(number_at(&game, 0, 0), Some(Number::from_exponent(2)));
+        assert_eq!(number_at(&game, 1, 0), Some(Number::from_exponent(2)));
+        assert_eq!(number_at(&game, 2, 0), None);
+    }
+
+    #[test]
+    fn merges_towards_bottom_and_right() {
+        let mut game = game_with(&[
+            (Number::from_exponent(2), 2, 0),
+            (Number::from_exponent(2), 2, 3),
+            (Number::from_exponent(4), 0, 1),
+            (Number::from_exponent(4), 1, 1),
+        ]);
+
+        move_and_merge(&mut game, Direction::BOTTOM);
+        assert_eq!(game.score(), 8);
+        assert_eq!(number_at(&game, 2, 3), Some(Number::from_exponent(3)));
+
+        move_and_merge(&mut game, Direction::RIGHT);
+        assert_eq!(game.score(), 8 + 32);
+        assert_eq!(number_at(&game, 2, 3), Some(Number::from_exponent(5)));
+        assert_eq!(number_at(&game, 3, 3), Some(Number::from_exponent(3)));
+    }
+
+    #[test]
+    fn moves_span_the_configured_width_and_height() {
+        let config = GameConfig {
+            board: BoardConfig::new(6, 3).unwrap(),
+            ..GameConfig::default()
+        };
+        let mut game = game_with_config(
+            config,
+            &[
+                (Number::from_exponent(1), 0, 0),
+                (Number::from_exponent(1), 5, 0),
+                (Number::from_exponent(2), 5, 2),
+            ],
+        );
+
+        move_and_merge(&mut game, Direction::RIGHT);
+        assert_eq!(game.score(), 4);
+        assert_eq!(number_at(&game, 5, 0), Some(Number::from_exponent(2)));
+
+        move_and_merge(&mut game, Direction::BOTTOM);
+        assert_eq!(game.score(), 4 + 8);
+        assert_eq!(number_at(&game, 5, 2), Some(Number::from_exponent(3)));
+    }
+
+    #[test]
+    fn apply_adds_one_random_block() {
+        let mut game = game_with(&[
+            (Number::from_exponent(1), 0, 0),
+            (Number::from_exponent(1), 1, 0),
+        ]);
+
+        let turn = match game.apply(Direction::LEFT) {
+            MoveOutcome::Moved(turn) => turn,
+            MoveOutcome::Blocked => panic!("Expected the move to succeed"),
+        };
+
+        let (id, number, position) = turn.spawned.unwrap();
+        assert_eq!(game.board().blocks().len(), 2);
+        assert_eq!(game.board().get(position.x, position.y), Some(id));
+        assert!(number == Number::MIN || number == Number::MIN.next());
+        assert_eq!(game.score(), 4);
+    }
+
+    #[test]
+    fn blocked_move_changes_nothing() {
+        let mut game = game_with(&[(Number::from_exponent(1), 0, 0)]);
+
+        assert!(matches!(game.apply(Direction::LEFT), MoveOutcome::Blocked));
+        assert_eq!(game.board().blocks().len(), 1);
+        assert!(!game.undo());
+    }
+
+    #[test]
+    fn filling_the_last_free_cell_ends_the_game() {
+        let config = GameConfig {
+            board: BoardConfig::new(2, 2).unwrap(),
+            ..GameConfig::default()
+        };
+        let mut game = game_with_config(
+            config,
+            &[
+                (Number::from_exponent(3), 0, 0),
+                (Number::from_exponent(4), 1, 0),
+                (Number::from_exponent(5), 0, 1),
+            ],
+        );
+
+        match game.apply(Direction::RIGHT) {
+            MoveOutcome::Moved(turn) => assert!(turn.game_over),
+            MoveOutcome::Blocked => panic!("Expected the move to succeed"),
+        }
+        assert!(game.is_game_over());
+        assert!(Direction::ALL.iter().all(|d| !game.can_move(*d)));
+
+        assert!(game.undo());
+        assert!(!game.is_game_over());
+    }
+
+    #[test]
+    fn same_seed_and_moves_produce_same_game() {
+        let config = GameConfig {
+            rng: RngConfig { seed: Some(2048) },
+            ..GameConfig::default()
+        };
+        let play = || {
+            let mut game = Game::new(config);
+            for direction in Direction::ALL.iter().cycle().take(40) {
+                game.apply(*direction);
+            }
+            format!("{:?} {}", game.board().blocks(), game.score())
+        };
+
+        assert_eq!(play(), play());
+    }
+
+    #[test]
+    fn restart_resets_score_and_keeps_board_size() {
+        let config = GameConfig {
+            board: BoardConfig::new(5, 3).unwrap(),
+            ..GameConfig::default()
+        };
+        let mut game = game_with_config(
+            config,
+            &[
+                (Number::from_exponent(1), 0, 0),
+                (Number::from_exponent(1), 1, 0),
+            ],
+        );
+        move_and_merge(&mut game, Direction::LEFT);
+        let seed = game.seed();
+
+        game.restart();
+
+        assert_eq!(game.score(), 0);
+        assert_eq!(game.board().blocks().len(), 1);
+        assert_eq!(game.board().width(), 5);
+        assert_eq!(game.board().height(), 3);
+        assert_ne!(game.seed(), seed);
+    }
+
+    #[test]
+    fn board_config_rejects_unsupported_sizes() {
+        assert!(BoardConfig::new(1, 4).is_err());
+        assert!(BoardConfig::new(4, BoardConfig::MAX_SIZE + 1).is_err());
+        assert!(BoardConfig::new(3, 3).is_ok());
+    }
+
+    #[test]
+    fn board_config_is_read_width_first() {
+        assert_eq!("5x3".parse::<BoardConfig>(), BoardConfig::new(5, 3));
+        assert!("5x1".parse::<BoardConfig>().is_err());
+        assert!("5".parse::<BoardConfig>().is_err());
+        assert!("5x".parse::<BoardConfig>().is_err());
+        assert!("ax4".parse::<BoardConfig>().is_err());
+    }
+
+    #[test]
+    fn undo_restores_board_score_and_ids() {
+        let mut game = game_with(&[
+            (Number::from_exponent(1), 0, 0),
+            (Number::from_exponent(1), 1, 0),
+        ]);
+        let current_id = game.current_id();
+
+        game.apply(Direction::LEFT);
+        assert!(game.undo());
+
+        assert_eq!(game.score(), 0);
+        assert_eq!(game.current_id(), current_id);
+        assert_eq!(game.board().blocks().len(), 2);
+        assert_eq!(number_at(&game, 0, 0), Some(Number::from_exponent(1)));
+        assert_eq!(number_at(&game, 1, 0), Some(Number::from_exponent(1)));
+        assert!(!game.undo());
+    }
+
+    #[test]
+    fn history_depth_limits_undo() {
+        let config = GameConfig {
+            undo: UndoConfig {
+                history_depth: Some(2),
+                undos_per_game: None,
+            },
+            ..GameConfig::default()
+        };
+        let mut game = game_with_config(config, &[(Number::from_exponent(1), 0, 0)]);
+
+        for direction in [Direction::RIGHT, Direction::BOTTOM, Direction::LEFT].iter() {
+            move_and_merge(&mut game, *direction);
+        }
+
+        assert!(game.undo());
+        assert!(game.undo());
+        assert!(!game.undo());
+        assert_eq!(number_at(&game, 3, 0), Some(Number::from_exponent(1)));
+    }
+
+    #[test]
+    fn undos_per_game_are_limited_and_reset_on_restart() {
+        let config = GameConfig {
+            undo: UndoConfig {
+                history_depth: None,
+                undos_per_game: Some(1),
+            },
+            ..GameConfig::default()
+        };
+        let mut game = game_with_config(config, &[(Number::from_exponent(1), 0, 0)]);
+
+        move_and_merge(&mut game, Direction::RIGHT);
+        move_and_merge(&mut game, Direction::LEFT);
+
+        assert!(game.undo());
+        assert!(!game.undo());
+
+        game.restart();
+        let direction = *Direction::ALL.iter().find(|d| game.can_move(**d)).unwrap();
+        game.apply(direction);
+        assert!(game.undo());
+    }
+
+    #[test]
+    fn disabled_undo_keeps_no_history() {
+        let config = GameConfig {
+            undo: UndoConfig::disabled(),
+            ..GameConfig::default()
+        };
+        let mut game = game_with_config(config, &[(Number::from_exponent(1), 0, 0)]);
+
+        move_and_merge(&mut game, Direction::RIGHT);
+
+        assert!(!game.undo());
+    }
+
+    #[test]
+    fn next_game_seed_follows_from_first_seed() {
+        let mut first = GameRng::new(7);
+        let mut second = GameRng::new(7);
+
+        first.next_game();
+        second.next_game();
+
+        assert_eq!(first.seed(), second.seed());
+        assert_ne!(first.seed(), 7);
+    }
+
+    #[test]
+    fn merging_into_target_wins_once() {
+        let mut game = game_with(&[
+            (Number::from_exponent(10), 0, 0),
+            (Number::from_exponent(10), 1, 0),
+            (Number::from_exponent(10), 0, 1),
+            (Number::from_exponent(10), 1, 1),
+        ]);
+
+        let turn = move_and_merge(&mut game, Direction::LEFT);
+        assert!(turn.won);
+        assert!(game.has_won());
+
+        let turn = move_and_merge(&mut game, Direction::TOP);
+        assert!(!turn.won);
+        assert_eq!(number_at(&game, 0, 0), Some(Number::from_exponent(12)));
+    }
+
+    #[test]
+    fn undoing_the_win_lets_it_be_won_again() {
+        let mut game = game_with(&[
+            (Number::from_exponent(10), 0, 0),
+            (Number::from_exponent(10), 1, 0),
+        ]);
+
+        assert!(move_and_merge(&mut game, Direction::LEFT).won);
+        assert!(game.undo());
+        assert!(!game.has_won());
+
+        assert!(move_and_merge(&mut game, Direction::LEFT).won);
+        assert!(game.has_won());
+    }
+
+    #[test]
+    fn win_target_is_configurable() {
+        let config = GameConfig {
+            win: WinConfig {
+                target: Number::from_exponent(3),
+            },
+            ..GameConfig::default()
+        };
+        let mut game = game_with_config(
+            config,
+            &[
+                (Number::from_exponent(2), 0, 0),
+                (Number::from_exponent(2), 1, 0),
+            ],
+        );
+
+        assert!(move_and_merge(&mut game, Direction::LEFT).won);
+
+        game.restart();
+        assert!(!game.has_won());
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::position_map::{Id, PositionMap};
+
+/// Limits on undo. `None` means unlimited, zero disables undo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UndoConfig {
+    pub history_depth: Option<usize>,
+    pub undos_per_game: Option<usize>,
+}
+
+impl UndoConfig {
+    pub fn disabled() -> Self {
+        Self {
+            history_depth: Some(0),
+            undos_per_game: Some(0),
+        }
+    }
+}
+
+impl Default for UndoConfig {
+    fn default() -> Self {
+        Self {
+            history_depth: Some(16),
+            undos_per_game: None,
+        }
+    }
+}
+
+/// State of the game right before a move
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub position_map: PositionMap,
+    pub score: u64,
+    pub current_id: Id,
+    /// Undoing the winning move lets the target be reached again
+    pub has_won: bool,
+}
+
+#[derive(Clone)]
+pub struct History {
+    config: UndoConfig,
+    snapshots: VecDeque<Snapshot>,
+    undos_used: usize,
+}
+
+impl History {
+    pub fn new(config: UndoConfig) -> Self {
+        Self {
+            config: config,
+            snapshots: VecDeque::new(),
+            undos_used: 0,
+        }
+    }
+
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.config.history_depth == Some(0) {
+            return;
+        }
+
+        self.snapshots.push_back(snapshot);
+        if let Some(depth) = self.config.history_depth {
+            while self.snapshots.len() > depth {
+                self.snapshots.pop_front();
+            }
+        }
+    }
+
+    /// Takes the most recent snapshot, if any are left and the game still has undos
+    pub fn pop(&mut self) -> Option<Snapshot> {
+        if self.undos_left() == Some(0) {
+            return None;
+        }
+
+        let snapshot = self.snapshots.pop_back()?;
+        self.undos_used += 1;
+        Some(snapshot)
+    }
+
+    pub fn undos_left(&self) -> Option<usize> {
+        self.config
+            .undos_per_game
+            .map(|limit| limit.saturating_sub(self.undos_used))
+    }
+
+    /// Forgets every snapshot and gives back the undos of the current game
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.undos_used = 0;
+    }
+}
+
+impl fmt::Debug for History {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("History")
+            .field("config", &self.config)
+            .field("snapshots", &self.snapshots.len())
+            .field("undos_used", &self.undos_used)
+            .finish()
+    }
+}
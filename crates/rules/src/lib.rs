@@ -0,0 +1,19 @@
+//! Rules of 2048 without any engine attached, so they can drive the game,
+//! bots, servers and tests alike.
+
+pub mod ai;
+pub mod bitboard;
+pub mod game;
+pub mod history;
+pub mod number;
+pub mod position_map;
+pub mod replay;
+pub mod rng;
+pub mod save;
+pub mod strategy;
+
+pub use bitboard::Bitboard;
+pub use game::{BoardConfig, Game, GameConfig, Merge, MoveOutcome, Turn, WinConfig};
+pub use number::Number;
+pub use position_map::{Direction, Id, Position, PositionMap};
+pub use replay::Replay;
@@ -0,0 +1,105 @@
+use std::fmt;
+
+/// Value of a block, stored as the power of two it represents so blocks can
+/// keep growing without an upper limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Number {
+    exponent: u32,
+}
+
+impl Number {
+    /// The smallest block, 2
+    pub const MIN: Number = Number::from_exponent(1);
+
+    pub const fn from_exponent(exponent: u32) -> Self {
+        Self { exponent: exponent }
+    }
+
+    pub fn from_value(value: u64) -> Option<Self> {
+        if value >= 2 && value.is_power_of_two() {
+            Some(Self::from_exponent(value.trailing_zeros()))
+        } else {
+            None
+        }
+    }
+
+    pub fn exponent(&self) -> u32 {
+        self.exponent
+    }
+
+    /// Numeric value of the block, saturating at `u64::MAX` past 2^63.
+    /// Use the `Display` implementation for the exact value of any block.
+    pub fn value(&self) -> u64 {
+        1u64.checked_shl(self.exponent).unwrap_or(u64::MAX)
+    }
+
+    pub fn next(&self) -> Self {
+        Self::from_exponent(self.exponent + 1)
+    }
+}
+
+impl fmt::Display for Number {
+    /// Writes the exact decimal value, doubling digit by digit past what fits in a u64
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.exponent < 64 {
+            return write!(f, "{}", self.value());
+        }
+
+        // Little endian decimal digits
+        let mut digits: Vec<u8> = vec![1];
+        for _ in 0..self.exponent {
+            let mut carry = 0;
+            for digit in digits.iter_mut() {
+                let doubled = *digit * 2 + carry;
+                *digit = doubled % 10;
+                carry = doubled / 10;
+            }
+            if carry > 0 {
+                digits.push(carry);
+            }
+        }
+
+        let text: String = digits.iter().rev().map(|d| (b'0' + d) as char).collect();
+        f.write_str(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_keeps_growing_past_old_maximum() {
+        let number = Number::from_value(131072).unwrap();
+
+        assert_eq!(number.next().value(), 262144);
+        assert!(number.next() > number);
+    }
+
+    #[test]
+    fn from_value_accepts_powers_of_two_only() {
+        assert_eq!(Number::from_value(2), Some(Number::MIN));
+        assert_eq!(Number::from_value(1 << 40).unwrap().exponent(), 40);
+        assert_eq!(Number::from_value(1), None);
+        assert_eq!(Number::from_value(12), None);
+    }
+
+    #[test]
+    fn displays_exact_value_for_any_exponent() {
+        assert_eq!(Number::from_exponent(11).to_string(), "2048");
+        assert_eq!(Number::from_exponent(63).to_string(), "9223372036854775808");
+        assert_eq!(
+            Number::from_exponent(64).to_string(),
+            "18446744073709551616"
+        );
+        assert_eq!(
+            Number::from_exponent(100).to_string(),
+            "1267650600228229401496703205376"
+        );
+    }
+
+    #[test]
+    fn value_saturates_past_u64() {
+        assert_eq!(Number::from_exponent(64).value(), u64::MAX);
+    }
+}
@@ -0,0 +1,453 @@
+use crate::number::Number;
+use ndarray::Array2;
+use rand::Rng;
+use std::collections::HashMap;
+
+pub type Id = i32;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Position {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Position {
+    pub fn from(x: usize, y: usize) -> Self {
+        Self {
+            x: x.try_into().unwrap(),
+            y: y.try_into().unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    LEFT,
+    RIGHT,
+    TOP,
+    BOTTOM,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::LEFT,
+        Direction::RIGHT,
+        Direction::TOP,
+        Direction::BOTTOM,
+    ];
+
+    /// Step taken by a block moving one cell in this direction
+    pub fn offset(&self) -> (i32, i32) {
+        match self {
+            Direction::LEFT => (-1, 0),
+            Direction::RIGHT => (1, 0),
+            Direction::TOP => (0, -1),
+            Direction::BOTTOM => (0, 1),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PositionMap {
+    width: i32,
+    height: i32,
+    positions: Array2<Option<Id>>,
+    blocks: HashMap<Id, Number>,
+}
+
+impl PositionMap {
+    pub fn new(width: i32, height: i32) -> Self {
+        Self {
+            width: width,
+            height: height,
+            positions: Array2::<Option<Id>>::from_elem((width as usize, height as usize), None),
+            blocks: HashMap::new(),
+        }
+    }
+
+    pub fn new_with_existing_blocks(&self) -> Self {
+        Self {
+            blocks: self.blocks.clone(),
+            ..Self::new(self.width, self.height)
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn set(&mut self, x: i32, y: i32, id: Option<Id>) {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            panic!("Attempt to set out of bounds")
+        }
+
+        self.positions[[x as usize, y as usize]] = id
+    }
+
+    pub fn add_block(&mut self, id: Id, number: Number) {
+        self.blocks.insert(id, number);
+    }
+
+    pub fn delete_block(&mut self, target: Id) {
+        self.blocks.remove(&target);
+        if let Some(p) = self.find_position(target) {
+            self.set(p.x, p.y, None);
+        }
+    }
+
+    pub fn find_position(&self, target: Id) -> Option<Position> {
+        for ((x, y), value) in self.positions.indexed_iter() {
+            if let Some(id) = value {
+                if *id == target {
+                    return Some(Position::from(x, y));
+                }
+            }
+        }
+        None
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> Option<Id> {
+        if x < 0 || y < 0 || x >= self.width || y >= self.height {
+            return None;
+        }
+
+        self.positions[[x.try_into().unwrap(), y.try_into().unwrap()]]
+    }
+
+    fn get_number(&self, x: i32, y: i32) -> Option<Number> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+
+        if let Some(id) = self.get(x, y) {
+            if let Some(number) = self.blocks.get(&id) {
+                return Some(number.to_owned());
+            }
+        }
+        None
+    }
+
+    pub fn get_number_with_id(&self, id: Id) -> Option<Number> {
+        self.blocks.get(&id).copied()
+    }
+
+    pub fn get_random_free_position(&self, rng: &mut impl Rng) -> Option<Position> {
+        let quantity: i32 = self
+            .positions
+            .iter()
+            .filter(|i| i.is_none())
+            .count()
+            .try_into()
+            .unwrap();
+
+        if quantity == 0 {
+            return None;
+        }
+
+        let chosen = rng.gen_range(0..quantity);
+
+        let mut current: i32 = -1;
+        for ((x, y), value) in self.positions.indexed_iter() {
+            if value.is_none() {
+                current += 1;
+                if current == chosen {
+                    return Some(Position::from(x, y));
+                }
+            }
+        }
+        println!("No free positions found");
+        None
+    }
+
+    /// A move is legal when some block has an empty cell or an equal
+    /// block right next to it in the direction of the move.
+    pub fn can_move(&self, direction: Direction) -> bool {
+        let (dx, dy) = direction.offset();
+        for x in 0..self.width {
+            for y in 0..self.height {
+                let (next_x, next_y) = (x + dx, y + dy);
+                if next_x < 0 || next_y < 0 || next_x >= self.width || next_y >= self.height {
+                    continue;
+                }
+
+                if let Some(number) = self.get_number(x, y) {
+                    match self.get_number(next_x, next_y) {
+                        None => return true,
+                        Some(next) if next == number => return true,
+                        _ => (),
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    pub fn has_available_moves(&self) -> bool {
+        Direction::ALL
+            .iter()
+            .any(|direction| self.can_move(*direction))
+    }
+
+    /// Every block on the board with its number and position
+    pub fn blocks(&self) -> Vec<(Id, Number, Position)> {
+        self.positions
+            .indexed_iter()
+            .filter_map(|((x, y), value)| {
+                value.and_then(|id| {
+                    self.get_number_with_id(id)
+                        .map(|number| (id, number, Position::from(x, y)))
+                })
+            })
+            .collect()
+    }
+
+    pub fn has_any_blocks(&self) -> bool {
+        !self.blocks.is_empty()
+    }
+
+    pub fn get_not_empty_position_from(&self, direction: Direction, line: i32) -> Option<Position> {
+        match direction {
+            Direction::LEFT => {
+                for i in 0..self.width {
+                    if self.get(i, line).is_some() {
+                        return Some(Position { x: i, y: line });
+                    }
+                }
+            }
+            Direction::RIGHT => {
+                for i in (0..self.width).rev() {
+                    if self.get(i, line).is_some() {
+                        return Some(Position { x: i, y: line });
+                    }
+                }
+            }
+            Direction::TOP => {
+                for i in 0..self.height {
+                    if self.get(line, i).is_some() {
+                        return Some(Position { x: line, y: i });
+                    }
+                }
+            }
+            Direction::BOTTOM => {
+                for i in (0..self.height).rev() {
+                    if self.get(line, i).is_some() {
+                        return Some(Position { x: line, y: i });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn print_map(&self) {
+        // println!("Map: {:?}", self.positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BoardConfig, Game, GameConfig, MoveOutcome};
+    use proptest::prelude::*;
+
+    /// Board as rows of ranks, `None` being an empty cell
+    type Grid = Vec<Vec<Option<u8>>>;
+
+    fn number(rank: u8) -> Number {
+        Number::from_exponent(rank as u32 + 1)
+    }
+
+    fn game_from(grid: &Grid) -> Game {
+        let config = GameConfig {
+            board: BoardConfig::new(grid[0].len() as i32, grid.len() as i32).unwrap(),
+            ..GameConfig::default()
+        };
+        let blocks = grid.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter_map(move |(x, cell)| cell.map(|rank| (number(rank), Position::from(x, y))))
+        });
+        Game::from_blocks(config, blocks)
+    }
+
+    fn grid_from(game: &Game) -> Grid {
+        let map = game.board();
+        (0..map.height())
+            .map(|y| {
+                (0..map.width())
+                    .map(|x| map.get_number(x, y).map(|n| (n.exponent() - 1) as u8))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Brute force reference: slide and merge every line of a plain grid.
+    fn oracle_move(grid: &Grid, direction: Direction) -> Grid {
+        let height = grid.len();
+        let width = grid[0].len();
+        let lines: Vec<Vec<(usize, usize)>> = match direction {
+            Direction::LEFT => (0..height)
+                .map(|y| (0..width).map(|x| (x, y)).collect())
+                .collect(),
+            Direction::RIGHT => (0..height)
+                .map(|y| (0..width).rev().map(|x| (x, y)).collect())
+                .collect(),
+            Direction::TOP => (0..width)
+                .map(|x| (0..height).map(|y| (x, y)).collect())
+                .collect(),
+            Direction::BOTTOM => (0..width)
+                .map(|x| (0..height).rev().map(|y| (x, y)).collect())
+                .collect(),
+        };
+
+        let mut result = vec![vec![None; width]; height];
+        for line in lines {
+            let tiles: Vec<u8> = line.iter().filter_map(|(x, y)| grid[*y][*x]).collect();
+            let mut merged: Vec<u8> = vec![];
+            let mut i = 0;
+            while i < tiles.len() {
+                if i + 1 < tiles.len() && tiles[i] == tiles[i + 1] {
+                    merged.push(tiles[i] + 1);
+                    i += 2;
+                } else {
+                    merged.push(tiles[i]);
+                    i += 1;
+                }
+            }
+            for (rank, (x, y)) in merged.into_iter().zip(line.iter()) {
+                result[*y][*x] = Some(rank);
+            }
+        }
+        result
+    }
+
+    fn grid_strategy() -> impl Strategy<Value = Grid> {
+        (2usize..=6, 2usize..=6).prop_flat_map(|(width, height)| {
+            prop::collection::vec(
+                prop::collection::vec(prop::option::weighted(0.7, 0u8..4), width),
+                height,
+            )
+        })
+    }
+
+    fn available_moves(game: &Game) -> Vec<Direction> {
+        Direction::ALL
+            .iter()
+            .copied()
+            .filter(|direction| game.board().can_move(*direction))
+            .collect()
+    }
+
+    fn grid(rows: &[&[u8]]) -> Grid {
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| if *cell == 0 { None } else { Some(*cell - 1) })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn empty_board_has_no_moves() {
+        let game = game_from(&grid(&[&[0, 0], &[0, 0]]));
+        assert!(!game.board().has_available_moves());
+    }
+
+    #[test]
+    fn full_board_without_equal_neighbours_has_no_moves() {
+        let game = game_from(&grid(&[
+            &[1, 2, 1, 2],
+            &[2, 1, 2, 1],
+            &[1, 2, 1, 2],
+            &[2, 1, 2, 1],
+        ]));
+        assert!(available_moves(&game).is_empty());
+    }
+
+    #[test]
+    fn full_board_with_equal_neighbours_in_a_row_moves_sideways() {
+        let game = game_from(&grid(&[&[1, 1, 2], &[2, 3, 1], &[3, 1, 2]]));
+        assert_eq!(
+            available_moves(&game),
+            vec![Direction::LEFT, Direction::RIGHT]
+        );
+    }
+
+    #[test]
+    fn blocks_against_a_wall_only_move_away_from_it() {
+        let game = game_from(&grid(&[&[1, 0, 0], &[2, 0, 0], &[0, 0, 0]]));
+        assert_eq!(
+            available_moves(&game),
+            vec![Direction::RIGHT, Direction::BOTTOM]
+        );
+    }
+
+    #[test]
+    fn empty_neighbours_do_not_count_as_moves() {
+        let game = game_from(&grid(&[&[1, 0, 2], &[2, 3, 4]]));
+        assert_eq!(
+            available_moves(&game),
+            vec![Direction::LEFT, Direction::RIGHT, Direction::TOP]
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn can_move_matches_oracle(grid in grid_strategy()) {
+            let game = game_from(&grid);
+            for direction in Direction::ALL.iter() {
+                let changes_board = oracle_move(&grid, *direction) != grid;
+                prop_assert_eq!(game.board().can_move(*direction), changes_board);
+            }
+        }
+
+        #[test]
+        fn has_available_moves_matches_oracle(grid in grid_strategy()) {
+            let game = game_from(&grid);
+            let any_change = Direction::ALL
+                .iter()
+                .any(|direction| oracle_move(&grid, *direction) != grid);
+            prop_assert_eq!(game.board().has_available_moves(), any_change);
+        }
+
+        #[test]
+        fn move_blocks_matches_oracle(grid in grid_strategy(), index in 0usize..4) {
+            let direction = Direction::ALL[index];
+            let mut game = game_from(&grid);
+            let expected = oracle_move(&grid, direction);
+
+            match game.move_blocks(direction) {
+                Some(_) => prop_assert_eq!(grid_from(&game), expected),
+                None => prop_assert_eq!(&expected, &grid),
+            }
+        }
+
+        #[test]
+        fn apply_adds_one_block_to_the_moved_board(grid in grid_strategy(), index in 0usize..4) {
+            let direction = Direction::ALL[index];
+            let mut game = game_from(&grid);
+            let expected = oracle_move(&grid, direction);
+
+            match game.apply(direction) {
+                MoveOutcome::Moved(_) => {
+                    let result = grid_from(&game);
+                    let differences: Vec<(Option<u8>, Option<u8>)> = expected
+                        .iter()
+                        .flatten()
+                        .zip(result.iter().flatten())
+                        .map(|(expected, result)| (*expected, *result))
+                        .filter(|(expected, result)| expected != result)
+                        .collect();
+                    prop_assert_eq!(differences.len(), 1);
+                    prop_assert!(matches!(differences[0], (None, Some(0)) | (None, Some(1))));
+                }
+                MoveOutcome::Blocked => prop_assert_eq!(&expected, &grid),
+            }
+        }
+    }
+}
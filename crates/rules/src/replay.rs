@@ -0,0 +1,441 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{BoardConfig, Game, GameConfig, MoveOutcome, Turn};
+use crate::history::UndoConfig;
+use crate::number::Number;
+use crate::position_map::{Direction, Position};
+use crate::rng::RngConfig;
+
+/// Bumped whenever the replay file layout changes, older replays are refused
+pub const REPLAY_VERSION: u32 = 1;
+
+/// A random block added to the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spawn {
+    pub number: Number,
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Spawn {
+    fn new(number: Number, position: Position) -> Self {
+        Self {
+            number: number,
+            x: position.x,
+            y: position.y,
+        }
+    }
+
+    fn from_turn(turn: &Turn) -> Option<Self> {
+        turn.spawned
+            .map(|(_, number, position)| Self::new(number, position))
+    }
+
+    fn parse(token: &str) -> Option<Self> {
+        let (number, position) = token.split_once('@')?;
+        let (x, y) = position.split_once(',')?;
+
+        Some(Self {
+            number: Number::from_value(number.parse().ok()?)?,
+            x: x.parse().ok()?,
+            y: y.parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for Spawn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{},{}", self.number, self.x, self.y)
+    }
+}
+
+/// One thing the player did during a recorded game
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    /// A move, with the block it spawned so a replay can be checked
+    Move {
+        direction: Direction,
+        spawned: Option<Spawn>,
+    },
+    Undo,
+}
+
+impl Step {
+    const UNDO: &'static str = "undo";
+
+    fn parse(token: &str) -> Option<Self> {
+        if token == Self::UNDO {
+            return Some(Step::Undo);
+        }
+
+        let direction = match token.chars().next()? {
+            'L' => Direction::LEFT,
+            'R' => Direction::RIGHT,
+            'U' => Direction::TOP,
+            'D' => Direction::BOTTOM,
+            _ => return None,
+        };
+        let spawned = match &token[1..] {
+            "" => None,
+            spawn => Some(Spawn::parse(spawn)?),
+        };
+
+        Some(Step::Move {
+            direction: direction,
+            spawned: spawned,
+        })
+    }
+}
+
+impl fmt::Display for Step {
+    /// A short token such as `L2@1,3`, a move left that spawned a 2 at 1, 3
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Move { direction, spawned } => {
+                let letter = match direction {
+                    Direction::LEFT => 'L',
+                    Direction::RIGHT => 'R',
+                    Direction::TOP => 'U',
+                    Direction::BOTTOM => 'D',
+                };
+                write!(f, "{}", letter)?;
+                match spawned {
+                    Some(spawn) => write!(f, "{}", spawn),
+                    None => Ok(()),
+                }
+            }
+            Step::Undo => write!(f, "{}", Self::UNDO),
+        }
+    }
+}
+
+/// Why a replay can't be played back on this version of the rules
+#[derive(Debug, Clone, PartialEq)]
+pub enum ReplayError {
+    /// The game started with another block
+    Start {
+        expected: Option<Spawn>,
+        found: Option<Spawn>,
+    },
+    /// A move spawned another block than the recorded one. Moves are counted from 1.
+    Spawn {
+        step: usize,
+        direction: Direction,
+        expected: Option<Spawn>,
+        found: Option<Spawn>,
+    },
+    /// A recorded move couldn't be made
+    Blocked { step: usize, direction: Direction },
+    /// A recorded undo had nothing to undo
+    Undo { step: usize },
+}
+
+fn describe(spawn: &Option<Spawn>) -> String {
+    match spawn {
+        Some(spawn) => format!("{} at {}, {}", spawn.number, spawn.x, spawn.y),
+        None => "nothing".to_string(),
+    }
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Start { expected, found } => write!(
+                f,
+                "game started with {} but the replay expects {}",
+                describe(found),
+                describe(expected)
+            ),
+            ReplayError::Spawn {
+                step,
+                direction,
+                expected,
+                found,
+            } => write!(
+                f,
+                "move {} ({:?}) spawned {} but the replay expects {}",
+                step,
+                direction,
+                describe(found),
+                describe(expected)
+            ),
+            ReplayError::Blocked { step, direction } => {
+                write!(f, "move {} ({:?}) can't be made", step, direction)
+            }
+            ReplayError::Undo { step } => write!(f, "move {} undoes nothing", step),
+        }
+    }
+}
+
+/// A game kept as its seed and what the player did, small enough to share
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "ReplayFile", into = "ReplayFile")]
+pub struct Replay {
+    pub board: BoardConfig,
+    pub seed: u64,
+    /// The block the game started with
+    pub start: Option<Spawn>,
+    pub steps: Vec<Step>,
+}
+
+impl Replay {
+    /// Starts recording a game that has just begun
+    pub fn start(game: &Game) -> Self {
+        let start = game
+            .board()
+            .blocks()
+            .first()
+            .map(|(_, number, position)| Spawn::new(*number, *position));
+
+        Self {
+            board: game.config().board,
+            seed: game.seed(),
+            start: start,
+            steps: vec![],
+        }
+    }
+
+    pub fn record_move(&mut self, direction: Direction, turn: &Turn) {
+        self.steps.push(Step::Move {
+            direction: direction,
+            spawned: Spawn::from_turn(turn),
+        });
+    }
+
+    pub fn record_undo(&mut self) {
+        self.steps.push(Step::Undo);
+    }
+
+    /// Setup reproducing the recorded game. Any recorded undo could be made,
+    /// so undos are left unlimited.
+    pub fn config(&self) -> GameConfig {
+        GameConfig {
+            board: self.board,
+            undo: UndoConfig {
+                history_depth: None,
+                undos_per_game: None,
+            },
+            rng: RngConfig {
+                seed: Some(self.seed),
+            },
+            ..GameConfig::default()
+        }
+    }
+
+    /// The game after its first `steps` steps, checking every spawn on the way
+    pub fn play(&self, steps: usize) -> Result<Game, ReplayError> {
+        let mut game = Game::new(self.config());
+        let start = Replay::start(&game).start;
+        if start != self.start {
+            return Err(ReplayError::Start {
+                expected: self.start,
+                found: start,
+            });
+        }
+
+        for (index, step) in self.steps.iter().take(steps).enumerate() {
+            let step_number = index + 1;
+            match *step {
+                Step::Move { direction, spawned } => match game.apply(direction) {
+                    MoveOutcome::Moved(turn) => {
+                        let found = Spawn::from_turn(&turn);
+                        if found != spawned {
+                            return Err(ReplayError::Spawn {
+                                step: step_number,
+                                direction: direction,
+                                expected: spawned,
+                                found: found,
+                            });
+                        }
+                    }
+                    MoveOutcome::Blocked => {
+                        return Err(ReplayError::Blocked {
+                            step: step_number,
+                            direction: direction,
+                        })
+                    }
+                },
+                Step::Undo => {
+                    if !game.undo() {
+                        return Err(ReplayError::Undo { step: step_number });
+                    }
+                }
+            }
+        }
+
+        Ok(game)
+    }
+
+    /// Plays the whole replay through, failing at the first step that diverges
+    pub fn verify(&self) -> Result<Game, ReplayError> {
+        self.play(self.steps.len())
+    }
+}
+
+/// Layout of a replay on disk, every step is a short token
+#[derive(Serialize, Deserialize)]
+struct ReplayFile {
+    version: u32,
+    width: i32,
+    height: i32,
+    seed: u64,
+    start: String,
+    steps: String,
+}
+
+impl From<Replay> for ReplayFile {
+    fn from(replay: Replay) -> Self {
+        let steps: Vec<String> = replay.steps.iter().map(|step| step.to_string()).collect();
+
+        Self {
+            version: REPLAY_VERSION,
+            width: replay.board.width,
+            height: replay.board.height,
+            seed: replay.seed,
+            start: replay
+                .start
+                .map(|spawn| spawn.to_string())
+                .unwrap_or_default(),
+            steps: steps.join(" "),
+        }
+    }
+}
+
+impl TryFrom<ReplayFile> for Replay {
+    type Error = String;
+
+    fn try_from(file: ReplayFile) -> Result<Self, Self::Error> {
+        if file.version != REPLAY_VERSION {
+            return Err(format!(
+                "replay version {} is not supported, expected {}",
+                file.version, REPLAY_VERSION
+            ));
+        }
+
+        let start = match file.start.as_str() {
+            "" => None,
+            start => Some(Spawn::parse(start).ok_or_else(|| format!("bad start {}", start))?),
+        };
+        let steps = file
+            .steps
+            .split_whitespace()
+            .map(|token| Step::parse(token).ok_or_else(|| format!("bad step {}", token)))
+            .collect::<Result<Vec<Step>, String>>()?;
+
+        Ok(Self {
+            board: BoardConfig::new(file.width, file.height)?,
+            seed: file.seed,
+            start: start,
+            steps: steps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded_game() -> (Game, Replay) {
+        let mut game = Game::new(GameConfig {
+            rng: RngConfig { seed: Some(7) },
+            ..GameConfig::default()
+        });
+        let mut replay = Replay::start(&game);
+
+        for (index, direction) in Direction::ALL.iter().cycle().take(40).enumerate() {
+            if let MoveOutcome::Moved(turn) = game.apply(*direction) {
+                replay.record_move(*direction, &turn);
+            }
+            if index % 10 == 9 && game.undo() {
+                replay.record_undo();
+            }
+        }
+
+        (game, replay)
+    }
+
+    #[test]
+    fn replay_reproduces_the_recorded_game() {
+        let (game, replay) = recorded_game();
+
+        let replayed = replay.verify().unwrap();
+
+        assert!(replay.steps.contains(&Step::Undo));
+        assert_eq!(
+            format!("{:?}", replayed.board().blocks()),
+            format!("{:?}", game.board().blocks())
+        );
+        assert_eq!(replayed.score(), game.score());
+    }
+
+    #[test]
+    fn replay_survives_a_round_trip_through_json() {
+        let (_, replay) = recorded_game();
+
+        let json = serde_json::to_string(&replay).unwrap();
+
+        assert_eq!(serde_json::from_str::<Replay>(&json).unwrap(), replay);
+    }
+
+    #[test]
+    fn diverging_spawn_names_the_move() {
+        let (_, mut replay) = recorded_game();
+        let (index, direction, spawned) = replay
+            .steps
+            .iter()
+            .enumerate()
+            .find_map(|(index, step)| match step {
+                Step::Move {
+                    direction,
+                    spawned: Some(spawned),
+                } if index >= 5 => Some((index, *direction, *spawned)),
+                _ => None,
+            })
+            .unwrap();
+        let tampered = Spawn {
+            number: spawned.number.next(),
+            ..spawned
+        };
+        replay.steps[index] = Step::Move {
+            direction: direction,
+            spawned: Some(tampered),
+        };
+
+        let error = replay.verify().unwrap_err();
+
+        assert_eq!(
+            error,
+            ReplayError::Spawn {
+                step: index + 1,
+                direction: direction,
+                expected: Some(tampered),
+                found: Some(spawned),
+            }
+        );
+        assert!(error
+            .to_string()
+            .starts_with(&format!("move {} ", index + 1)));
+    }
+
+    #[test]
+    fn replay_from_another_seed_fails_at_the_start() {
+        let (_, mut replay) = recorded_game();
+        replay.seed += 1;
+
+        assert!(replay.verify().is_err());
+    }
+
+    #[test]
+    fn unknown_step_is_refused() {
+        let (_, replay) = recorded_game();
+        let json =
+            serde_json::to_string(&replay)
+                .unwrap()
+                .replacen("\"steps\":\"", "\"steps\":\"X ", 1);
+
+        assert!(serde_json::from_str::<Replay>(&json).is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use rand::{RngCore, SeedableRng};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+/// Seed for the first game, a random one is picked when it isn't set.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RngConfig {
+    pub seed: Option<u64>,
+}
+
+/// Random number generator behind every block spawn. A game is fully
+/// determined by its seed and the moves played.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRng {
+    seed: u64,
+    rng: Pcg32,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed: seed,
+            rng: Pcg32::seed_from_u64(seed),
+        }
+    }
+
+    pub fn from_config(config: &RngConfig) -> Self {
+        Self::new(config.seed.unwrap_or_else(rand::random))
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Reseeds for a new game, the next seed comes from this generator so a
+    /// run of games is reproducible from the first seed.
+    pub fn next_game(&mut self) {
+        *self = Self::new(self.rng.next_u64());
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
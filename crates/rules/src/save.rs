@@ -0,0 +1,172 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::Game;
+use crate::number::Number;
+use crate::position_map::{Id, PositionMap};
+use crate::rng::GameRng;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedBlock {
+    pub id: Id,
+    pub exponent: u32,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Everything needed to carry on with a game. The undo history is not kept.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub width: i32,
+    pub height: i32,
+    pub blocks: Vec<SavedBlock>,
+    pub current_id: Id,
+    pub score: u64,
+    pub is_game_over: bool,
+    pub has_won: bool,
+    pub rng: GameRng,
+}
+
+impl Game {
+    pub fn to_saved(&self) -> SavedGame {
+        let blocks = self
+            .board()
+            .blocks()
+            .into_iter()
+            .map(|(id, number, position)| SavedBlock {
+                id: id,
+                exponent: number.exponent(),
+                x: position.x,
+                y: position.y,
+            })
+            .collect();
+
+        SavedGame {
+            width: self.board().width(),
+            height: self.board().height(),
+            blocks: blocks,
+            current_id: self.current_id(),
+            score: self.score(),
+            is_game_over: self.is_game_over(),
+            has_won: self.has_won(),
+            rng: self.rng().clone(),
+        }
+    }
+
+    /// Replaces the game with a saved one, leaving it untouched if the save is invalid
+    pub fn restore(&mut self, saved: SavedGame) -> Result<(), String> {
+        let width = self.board().width();
+        let height = self.board().height();
+        if saved.width != width || saved.height != height {
+            return Err(format!(
+                "saved board is {}x{} but the board is {}x{}",
+                saved.width, saved.height, width, height
+            ));
+        }
+
+        let mut position_map = PositionMap::new(width, height);
+        let mut ids = HashSet::new();
+        for block in saved.blocks.iter() {
+            if block.exponent == 0 {
+                return Err(format!("block {} is 1", block.id));
+            }
+            let number = Number::from_exponent(block.exponent);
+
+            if block.x < 0 || block.y < 0 || block.x >= width || block.y >= height {
+                return Err(format!("block {} is off the board", block.id));
+            }
+            if position_map.get(block.x, block.y).is_some() {
+                return Err(format!("two blocks at {}, {}", block.x, block.y));
+            }
+            if block.id < 0 || block.id >= saved.current_id || !ids.insert(block.id) {
+                return Err(format!("bad block id {}", block.id));
+            }
+
+            position_map.add_block(block.id, number);
+            position_map.set(block.x, block.y, Some(block.id));
+        }
+
+        self.replace(
+            position_map,
+            saved.current_id,
+            saved.score,
+            saved.is_game_over,
+            saved.has_won,
+            saved.rng,
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{BoardConfig, GameConfig};
+    use crate::position_map::Direction;
+    use crate::rng::RngConfig;
+    use rand::RngCore;
+
+    fn played_game() -> Game {
+        let mut game = Game::new(GameConfig {
+            rng: RngConfig { seed: Some(42) },
+            ..GameConfig::default()
+        });
+        for direction in Direction::ALL.iter() {
+            game.apply(*direction);
+        }
+        game
+    }
+
+    #[test]
+    fn restored_game_matches_saved_game() {
+        let game = played_game();
+
+        let mut restored = Game::new(GameConfig::default());
+        restored.restore(game.to_saved()).unwrap();
+
+        assert_eq!(
+            format!("{:?}", restored.board().blocks()),
+            format!("{:?}", game.board().blocks())
+        );
+        assert_eq!(restored.score(), game.score());
+        assert_eq!(restored.current_id(), game.current_id());
+        assert_eq!(restored.seed(), game.seed());
+        assert_eq!(
+            restored.rng().clone().next_u64(),
+            game.rng().clone().next_u64()
+        );
+    }
+
+    #[test]
+    fn invalid_save_leaves_game_untouched() {
+        let mut saved = played_game().to_saved();
+        saved.blocks.push(SavedBlock {
+            id: 0,
+            exponent: 3,
+            x: saved.blocks[0].x,
+            y: saved.blocks[0].y,
+        });
+
+        let mut target = Game::new(GameConfig {
+            rng: RngConfig { seed: Some(1) },
+            ..GameConfig::default()
+        });
+
+        assert!(target.restore(saved).is_err());
+        assert_eq!(target.seed(), 1);
+        assert_eq!(target.board().blocks().len(), 1);
+    }
+
+    #[test]
+    fn save_for_another_board_size_is_refused() {
+        let saved = played_game().to_saved();
+
+        let mut target = Game::new(GameConfig {
+            board: BoardConfig::new(5, 5).unwrap(),
+            ..GameConfig::default()
+        });
+
+        assert!(target.restore(saved).is_err());
+    }
+}
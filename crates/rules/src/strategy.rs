@@ -0,0 +1,152 @@
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use crate::ai::{self, Grid, SearchBoard, SearchConfig};
+use crate::game::{Game, MoveOutcome};
+use crate::position_map::{Direction, PositionMap};
+
+/// Picks the next move of a game
+pub trait Strategy {
+    /// A legal move, `None` when there is none left
+    fn choose(&mut self, board: &PositionMap) -> Option<Direction>;
+}
+
+fn legal_moves(board: &PositionMap) -> Vec<Direction> {
+    Direction::ALL
+        .iter()
+        .copied()
+        .filter(|direction| board.can_move(*direction))
+        .collect()
+}
+
+/// Any legal move, picked at random
+pub struct RandomStrategy {
+    rng: Pcg32,
+}
+
+impl RandomStrategy {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Pcg32::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Strategy for RandomStrategy {
+    fn choose(&mut self, board: &PositionMap) -> Option<Direction> {
+        legal_moves(board).choose(&mut self.rng).copied()
+    }
+}
+
+/// Keeps the biggest blocks in the bottom left corner: moves down or left
+/// whenever possible, taking the one that frees the most cells (down on a
+/// tie), and only moves right or up when it has to.
+pub struct CornerStrategy;
+
+impl Strategy for CornerStrategy {
+    fn choose(&mut self, board: &PositionMap) -> Option<Direction> {
+        let grid = Grid::from_position_map(board);
+        let free_cells = |direction: Direction| {
+            grid.slide(direction)
+                .map(|moved| moved.empty_positions().len())
+        };
+
+        [Direction::LEFT, Direction::BOTTOM]
+            .iter()
+            .filter_map(|direction| free_cells(*direction).map(|free| (free, *direction)))
+            .max_by_key(|(free, _)| *free)
+            .map(|(_, direction)| direction)
+            .or_else(|| {
+                [Direction::RIGHT, Direction::TOP]
+                    .iter()
+                    .copied()
+                    .find(|direction| board.can_move(*direction))
+            })
+    }
+}
+
+/// The move with the best expectimax score
+pub struct ExpectimaxStrategy {
+    pub config: SearchConfig,
+}
+
+impl Strategy for ExpectimaxStrategy {
+    fn choose(&mut self, board: &PositionMap) -> Option<Direction> {
+        ai::best_move(board, &self.config)
+    }
+}
+
+/// Plays until no move is left, returning the number of moves made
+pub fn play_out(game: &mut Game, strategy: &mut dyn Strategy) -> u32 {
+    let mut moves = 0;
+    while let Some(direction) = strategy.choose(game.board()) {
+        match game.apply(direction) {
+            MoveOutcome::Moved(_) => moves += 1,
+            MoveOutcome::Blocked => panic!("Strategy chose {:?} which can't move", direction),
+        }
+    }
+    moves
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameConfig;
+    use crate::number::Number;
+    use crate::rng::RngConfig;
+
+    fn game(seed: u64) -> Game {
+        Game::new(GameConfig {
+            rng: RngConfig { seed: Some(seed) },
+            ..GameConfig::default()
+        })
+    }
+
+    #[test]
+    fn every_strategy_plays_a_game_to_the_end() {
+        let strategies: Vec<Box<dyn Strategy>> = vec![
+            Box::new(RandomStrategy::new(1)),
+            Box::new(CornerStrategy),
+            Box::new(ExpectimaxStrategy {
+                config: SearchConfig {
+                    depth: 1,
+                    time_budget: None,
+                },
+            }),
+        ];
+
+        for mut strategy in strategies {
+            let mut game = game(4);
+            let moves = play_out(&mut game, strategy.as_mut());
+
+            assert!(moves > 0);
+            assert!(game.is_game_over());
+        }
+    }
+
+    #[test]
+    fn random_strategy_is_reproducible() {
+        let play = || {
+            let mut game = game(4);
+            play_out(&mut game, &mut RandomStrategy::new(8));
+            game.score()
+        };
+
+        assert_eq!(play(), play());
+    }
+
+    #[test]
+    fn corner_strategy_prefers_down_then_left_then_right() {
+        let choose = |x: i32, y: i32| {
+            let mut board = PositionMap::new(4, 4);
+            board.add_block(0, Number::MIN);
+            board.set(x, y, Some(0));
+            CornerStrategy.choose(&board)
+        };
+
+        assert_eq!(choose(1, 1), Some(Direction::BOTTOM));
+        assert_eq!(choose(1, 3), Some(Direction::LEFT));
+        assert_eq!(choose(0, 3), Some(Direction::RIGHT));
+    }
+}
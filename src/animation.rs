@@ -1,86 +1,340 @@
-use std::ops::Add;
+use std::f32::consts::PI;
 
 use crate::{
-    events::{AnimationCompleted, BlocksMoved},
+    events::{AnimationCompleted, AnimationSkipRequested, BlockOrigin, BlocksMoved, LayoutChanged},
+    layout::Layout,
     stages::CustomStage,
-    ui_plugin::{self, number_renderer::Block},
+    ui_plugin::number_renderer::{Block, RenderNumber},
 };
 
 use bevy::prelude::*;
 
 pub struct AnimationPlugin;
 
-struct BlockMoveAnimation {
-    target: (f32, f32),
-    step: (f32, f32),
-    total_iter: usize,
-    current_iter: usize,
+/// Shape of an animation over time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseOutCubic,
+    /// Overshoots the target a little and settles back onto it
+    Back,
 }
 
-impl BlockMoveAnimation {
-    pub fn new(target: (f32, f32), total_iter: usize) -> Self {
+impl Easing {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "linear" => Ok(Easing::Linear),
+            "ease-out-cubic" => Ok(Easing::EaseOutCubic),
+            "back" => Ok(Easing::Back),
+            _ => Err(format!(
+                "Unknown easing {}, expected linear, ease-out-cubic or back",
+                value
+            )),
+        }
+    }
+
+    /// Progress along the animation for `t` between 0 and 1
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseOutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::Back => {
+                let overshoot = 1.70158;
+                let t = t - 1.0;
+                1.0 + (overshoot + 1.0) * t.powi(3) + overshoot * t.powi(2)
+            }
+        }
+    }
+}
+
+/// How long a move takes and how it eases, `speed` scales every animation
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationConfig {
+    /// Seconds a move takes at normal speed
+    pub duration: f32,
+    pub easing: Easing,
+    pub speed: f32,
+}
+
+impl Default for AnimationConfig {
+    fn default() -> Self {
         Self {
-            target: target,
-            step: (0., 0.),
-            total_iter: total_iter,
-            current_iter: 0,
+            duration: 0.15,
+            easing: Easing::EaseOutCubic,
+            speed: 1.0,
         }
     }
 }
 
-struct State {
-    running: bool,
-    timer: Timer,
+/// Moves a block from where it is to `to` over `duration` seconds
+struct Tween {
+    from: Option<(f32, f32)>,
+    to: (f32, f32),
+    elapsed: f32,
+    duration: f32,
+    easing: Easing,
+}
+
+impl Tween {
+    pub fn new(to: (f32, f32), config: &AnimationConfig) -> Self {
+        Self {
+            from: None,
+            to: to,
+            elapsed: 0.0,
+            duration: config.duration,
+            easing: config.easing,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Where the block is after `elapsed` seconds
+    fn position(&self, from: (f32, f32)) -> (f32, f32) {
+        if self.is_finished() {
+            return self.to;
+        }
+
+        let progress = self.easing.apply(self.elapsed / self.duration);
+        (
+            from.0 + (self.to.0 - from.0) * progress,
+            from.1 + (self.to.1 - from.1) * progress,
+        )
+    }
+}
+
+/// Seconds a new block takes to grow to full size at normal speed
+const SPAWN_DURATION: f32 = 0.12;
+/// Seconds a merged block takes to pulse at normal speed
+const MERGE_DURATION: f32 = 0.15;
+/// How much larger a merged block gets at the height of its pulse
+const MERGE_GROWTH: f32 = 0.2;
+
+/// Grows a new block from nothing, or makes a merged one pulse, around the
+/// centre of its cell
+pub struct Pop {
+    origin: BlockOrigin,
+    elapsed: f32,
+    cell: (f32, f32),
+    cell_size: f32,
+    font_size: f32,
+}
+
+impl Pop {
+    /// Blocks that were already on the board just appear
+    pub fn new(
+        origin: BlockOrigin,
+        cell: (f32, f32),
+        cell_size: f32,
+        font_size: f32,
+    ) -> Option<Self> {
+        if origin == BlockOrigin::Placed {
+            return None;
+        }
+
+        Some(Self {
+            origin: origin,
+            elapsed: 0.0,
+            cell: cell,
+            cell_size: cell_size,
+            font_size: font_size,
+        })
+    }
+
+    fn duration(&self) -> f32 {
+        match self.origin {
+            BlockOrigin::Merged => MERGE_DURATION,
+            _ => SPAWN_DURATION,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration()
+    }
+
+    /// Size of the block relative to its cell
+    fn scale(&self) -> f32 {
+        if self.is_finished() {
+            return 1.0;
+        }
+
+        let t = self.elapsed / self.duration();
+        match self.origin {
+            BlockOrigin::Merged => 1.0 + MERGE_GROWTH * (PI * t).sin(),
+            _ => Easing::EaseOutCubic.apply(t),
+        }
+    }
+
+    fn resize(&self, style: &mut Style, text: Option<Mut<Text>>) {
+        let size = self.cell_size * self.scale();
+        style.size = Size::new(Val::Px(size), Val::Px(size));
+
+        if let Some(mut text) = text {
+            // Glyphs can't be drawn at size zero
+            text.sections[0].style.font_size = f32::max(self.font_size * self.scale(), 1.0);
+        }
+    }
+
+    /// Keeps the block centred on its cell whatever its size
+    fn center(&self, style: &mut Style) {
+        let offset = self.cell_size * (1.0 - self.scale()) / 2.0;
+        style.position.left = Val::Px(self.cell.0 + offset);
+        style.position.top = Val::Px(self.cell.1 + offset);
+    }
+}
+
+/// Seconds a score popup stays on screen at normal speed
+const POPUP_DURATION: f32 = 0.6;
+/// How far a score popup drifts up
+const POPUP_RISE: f32 = 40.0;
+/// Room between popups that show up in quick succession
+const POPUP_SPACING: f32 = 20.0;
+
+/// A "+N" label that drifts up from the score and fades out
+#[derive(Debug, Clone, Copy)]
+pub struct ScorePopup {
+    elapsed: f32,
+    /// Where the popup starts, below zero when it has to make room under another one
+    start: f32,
 }
 
-impl State {
-    pub fn new() -> Self {
-        State {
-            running: false,
-            timer: Timer::from_seconds(0.01, true),
+impl ScorePopup {
+    /// A popup that starts under the lowest popup still close to the start,
+    /// `lowest` being how far that popup has risen
+    pub fn below(lowest: Option<f32>) -> Self {
+        let start = match lowest {
+            Some(lowest) => f32::min(0.0, lowest - POPUP_SPACING),
+            None => 0.0,
+        };
+
+        Self {
+            elapsed: 0.0,
+            start: start,
         }
     }
+
+    fn progress(&self) -> f32 {
+        f32::min(self.elapsed / POPUP_DURATION, 1.0)
+    }
+
+    /// How far the popup has drifted up from the score
+    pub fn rise(&self) -> f32 {
+        self.start + POPUP_RISE * Easing::EaseOutCubic.apply(self.progress())
+    }
+
+    fn alpha(&self) -> f32 {
+        1.0 - self.progress().powi(2)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.elapsed >= POPUP_DURATION
+    }
+}
+
+/// A move is being animated
+struct State {
+    running: bool,
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
 enum Label {
-    MovedListener,
+    SkipListener,
 }
 
 impl Plugin for AnimationPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.insert_resource(State::new())
+        app.world_mut()
+            .get_resource_or_insert_with(AnimationConfig::default);
+
+        // Tweens are added after the move was made and only show up in the next
+        // frame, so they run before anything else in it
+        app.insert_resource(State { running: false })
+            .add_system_to_stage(CustomStage::After, blocks_moved_listener.system())
+            .add_system_to_stage(CustomStage::Layout, layout_changed_listener.system())
             .add_system_to_stage(
-                CustomStage::After,
-                blocks_moved_listener.system().label(Label::MovedListener),
+                CustomStage::Before,
+                skip_requested_listener.system().label(Label::SkipListener),
             )
             .add_system_to_stage(
-                CustomStage::After,
-                run_animations.system().after(Label::MovedListener),
-            );
+                CustomStage::Before,
+                run_animations.system().after(Label::SkipListener),
+            )
+            // After the new blocks were spawned so they never show at full size first
+            .add_system_to_stage(CustomStage::After, run_pops.system())
+            .add_system(run_score_popups.system());
     }
 }
 
 fn blocks_moved_listener(
     mut commands: Commands,
+    mut state: ResMut<State>,
+    layout: Res<Layout>,
+    config: Res<AnimationConfig>,
     query: Query<(Entity, &Block)>,
     mut events: EventReader<BlocksMoved>,
 ) {
     for event in events.iter() {
+        state.running = true;
         for (entity, block) in query.iter() {
             for (id, target) in event.moves.iter() {
                 if *id == block.id {
-                    let target_x = ui_plugin::column_x(target.x.try_into().unwrap());
-                    let target_y = ui_plugin::row_y(target.y.try_into().unwrap());
+                    let target_x = layout.column_x(target.x);
+                    let target_y = layout.row_y(target.y);
 
-                    let animation = BlockMoveAnimation::new((target_x, target_y), 15);
-                    commands.entity(entity).insert(animation);
+                    let tween = Tween::new((target_x, target_y), &config);
+                    commands.entity(entity).insert(tween);
                 }
             }
         }
     }
 }
 
+/// Keeps running animations on their cells when the window is resized
+fn layout_changed_listener(
+    layout: Res<Layout>,
+    mut events: EventReader<LayoutChanged>,
+    mut tweens: Query<&mut Tween>,
+    mut pops: Query<(&Block, &mut Pop)>,
+) {
+    for event in events.iter() {
+        for mut tween in tweens.iter_mut() {
+            tween.to = layout.moved_from(&event.previous, tween.to);
+            tween.from = tween
+                .from
+                .map(|from| layout.moved_from(&event.previous, from));
+        }
+
+        for (block, mut pop) in pops.iter_mut() {
+            pop.cell = layout.moved_from(&event.previous, pop.cell);
+            pop.cell_size = layout.cell_size;
+            pop.font_size = block.number.text_size(layout.cell_size);
+        }
+    }
+}
+
+/// Finishes every running tween at once
+fn skip_requested_listener(
+    mut events: EventReader<AnimationSkipRequested>,
+    mut query: Query<&mut Tween>,
+) {
+    if events.iter().count() == 0 {
+        return;
+    }
+
+    for mut tween in query.iter_mut() {
+        tween.elapsed = tween.duration;
+    }
+}
+
+fn pixels(value: Val) -> Option<f32> {
+    match value {
+        Val::Px(value) => Some(value),
+        _ => None,
+    }
+}
+
 /**
  * Because everything was built out of UI elements, this means that
  * we don't have transforms for those elements, but instead Style
@@ -88,49 +342,149 @@ fn blocks_moved_listener(
  */
 fn run_animations(
     time: Res<Time>,
+    config: Res<AnimationConfig>,
     mut state: ResMut<State>,
     mut commands: Commands,
-    mut query: Query<(Entity, &mut Style, &mut BlockMoveAnimation)>,
+    mut query: Query<(Entity, &mut Style, &mut Tween)>,
     mut events: EventWriter<AnimationCompleted>,
 ) {
-    if state.timer.tick(time.delta()).just_finished() {
-        let mut count = 0;
-        for (entity, mut style, mut animation) in query.iter_mut() {
-            count += 1;
-            if animation.current_iter == 0 {
-                let divisor: u16 = animation.total_iter.try_into().unwrap();
-                let divisor: f32 = divisor.try_into().unwrap();
-                // Calculate step size on first loop
-                let step_x: f32 = if let Val::Px(value) = style.position.left {
-                    (animation.target.0 - value) / divisor
-                } else {
-                    panic!("Was not a pixel value");
-                };
-
-                let step_y: f32 = if let Val::Px(value) = style.position.top {
-                    (animation.target.1 - value) / divisor
-                } else {
-                    panic!("Was not a pixel value");
-                };
-
-                animation.step = (step_x, step_y);
+    if !state.running {
+        return;
+    }
+
+    let mut running = 0;
+    for (entity, mut style, mut tween) in query.iter_mut() {
+        // A block placed some other way than in pixels just jumps to its target
+        let to = tween.to;
+        let from = *tween.from.get_or_insert_with(|| {
+            match (pixels(style.position.left), pixels(style.position.top)) {
+                (Some(left), Some(top)) => (left, top),
+                _ => to,
             }
+        });
 
-            animation.current_iter += 1;
+        tween.elapsed += time.delta_seconds() * config.speed;
+        let (x, y) = tween.position(from);
+        style.position.left = Val::Px(x);
+        style.position.top = Val::Px(y);
 
-            let (x, y) = animation.step;
-            style.position.left = style.position.left.add(x);
-            style.position.top = style.position.top.add(y);
+        if tween.is_finished() {
+            commands.entity(entity).remove::<Tween>();
+        } else {
+            running += 1;
+        }
+    }
 
-            if animation.total_iter == animation.current_iter {
-                count -= 1;
-                commands.entity(entity).remove::<BlockMoveAnimation>();
-            }
+    if running == 0 {
+        state.running = false;
+        events.send(AnimationCompleted);
+    }
+}
+
+fn run_pops(
+    time: Res<Time>,
+    config: Res<AnimationConfig>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Style, &mut Pop, &Children, Option<&Tween>)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (entity, mut style, mut pop, children, tween) in query.iter_mut() {
+        let text = children
+            .iter()
+            .find(|child| texts.get_mut(**child).is_ok())
+            .and_then(|child| texts.get_mut(*child).ok());
+
+        // A move takes over the block's position, so the pop ends where it is
+        if tween.is_some() {
+            pop.elapsed = pop.duration();
+            pop.resize(&mut style, text);
+        } else {
+            pop.elapsed += time.delta_seconds() * config.speed;
+            pop.resize(&mut style, text);
+            pop.center(&mut style);
+        }
+
+        if pop.is_finished() {
+            commands.entity(entity).remove::<Pop>();
+        }
+    }
+}
+
+fn run_score_popups(
+    time: Res<Time>,
+    config: Res<AnimationConfig>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Style, &mut Text, &mut ScorePopup)>,
+) {
+    for (entity, mut style, mut text, mut popup) in query.iter_mut() {
+        popup.elapsed += time.delta_seconds() * config.speed;
+        if popup.is_finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
         }
 
-        if count == 0 {
-            state.running = false;
-            events.send(AnimationCompleted);
+        style.position.top = Val::Px(-popup.rise());
+        let alpha = popup.alpha();
+        text.sections[0].style.color.set_a(alpha);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_easing_starts_and_ends_on_its_endpoints() {
+        for easing in [Easing::Linear, Easing::EaseOutCubic, Easing::Back].iter() {
+            assert!(easing.apply(0.0).abs() < 1e-6);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-6);
         }
     }
+
+    #[test]
+    fn back_overshoots_before_settling() {
+        assert!(Easing::Back.apply(0.8) > 1.0);
+        assert!(Easing::EaseOutCubic.apply(0.8) < 1.0);
+    }
+
+    #[test]
+    fn new_blocks_grow_and_merged_blocks_pulse_back_to_full_size() {
+        let spawned = |elapsed| Pop {
+            elapsed: elapsed,
+            ..Pop::new(BlockOrigin::Spawned, (0.0, 0.0), 100.0, 50.0).unwrap()
+        };
+        let merged = |elapsed| Pop {
+            elapsed: elapsed,
+            ..Pop::new(BlockOrigin::Merged, (0.0, 0.0), 100.0, 50.0).unwrap()
+        };
+
+        assert_eq!(spawned(0.0).scale(), 0.0);
+        assert!(spawned(SPAWN_DURATION / 2.0).scale() < 1.0);
+        assert_eq!(spawned(SPAWN_DURATION).scale(), 1.0);
+        assert!(merged(MERGE_DURATION / 2.0).scale() > 1.0);
+        assert_eq!(merged(MERGE_DURATION).scale(), 1.0);
+        assert!(Pop::new(BlockOrigin::Placed, (0.0, 0.0), 100.0, 50.0).is_none());
+    }
+
+    #[test]
+    fn quick_popups_stack_under_each_other() {
+        let first = ScorePopup::below(None);
+        let second = ScorePopup::below(Some(first.rise()));
+        let risen = ScorePopup {
+            elapsed: POPUP_DURATION / 2.0,
+            ..ScorePopup::below(None)
+        };
+
+        assert_eq!(first.rise(), 0.0);
+        assert_eq!(second.rise(), -POPUP_SPACING);
+        assert_eq!(ScorePopup::below(Some(risen.rise())).rise(), 0.0);
+    }
+
+    #[test]
+    fn finished_tween_lands_exactly_on_its_target() {
+        let mut tween = Tween::new((120.5, 300.25), &AnimationConfig::default());
+        tween.elapsed = 1.0;
+
+        assert_eq!(tween.position((0.0, 0.0)), (120.5, 300.25));
+    }
 }
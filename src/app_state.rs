@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use crate::events::{
+    BoardRestored, ContinueRequested, GameContinued, GameOver, GameRestarted, GameWon,
+    MainMenuRequested, NewGameRequested, PauseToggleRequested, RestartRequested, ResumeDeclined,
+    ResumeRequested,
+};
+
+/// Screen the app is on, systems that only make sense on some of them are gated on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    MainMenu,
+    Playing,
+    /// Pushed on top of `Playing`
+    Paused,
+    GameOver,
+    Won,
+}
+
+/// Drives `State<AppState>`, starting from `start`
+pub struct AppStatePlugin {
+    pub start: AppState,
+}
+
+impl Plugin for AppStatePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_state(self.start)
+            .add_system(new_game_requested_listener.system())
+            .add_system(continue_requested_listener.system())
+            .add_system(pause_toggle_requested_listener.system())
+            .add_system(main_menu_requested_listener.system())
+            .add_system(game_listener.system());
+    }
+}
+
+/// Leaves whatever the app was showing for `next`, clearing a pause under it
+fn go_to(state: &mut State<AppState>, next: AppState) {
+    if *state.current() != next {
+        state
+            .overwrite_replace(next)
+            .expect("State differs from the current one");
+    }
+}
+
+fn new_game_requested_listener(
+    mut state: ResMut<State<AppState>>,
+    mut events: EventReader<NewGameRequested>,
+    mut declined: EventWriter<ResumeDeclined>,
+    mut restart: EventWriter<RestartRequested>,
+) {
+    for _ in events.iter() {
+        declined.send(ResumeDeclined);
+        restart.send(RestartRequested);
+        go_to(&mut state, AppState::Playing);
+    }
+}
+
+fn continue_requested_listener(
+    mut state: ResMut<State<AppState>>,
+    mut events: EventReader<ContinueRequested>,
+    mut resume: EventWriter<ResumeRequested>,
+) {
+    for _ in events.iter() {
+        // Takes up the saved game if there is one, otherwise the current game goes on
+        resume.send(ResumeRequested);
+        go_to(&mut state, AppState::Playing);
+    }
+}
+
+fn pause_toggle_requested_listener(
+    mut state: ResMut<State<AppState>>,
+    mut events: EventReader<PauseToggleRequested>,
+) {
+    for _ in events.iter() {
+        let result = match state.current() {
+            AppState::Playing => state.overwrite_push(AppState::Paused),
+            AppState::Paused => state.overwrite_pop(),
+            _ => continue,
+        };
+        if let Err(error) = result {
+            println!("Could not toggle pause: {:?}", error);
+        }
+    }
+}
+
+fn main_menu_requested_listener(
+    mut state: ResMut<State<AppState>>,
+    mut events: EventReader<MainMenuRequested>,
+) {
+    for _ in events.iter() {
+        go_to(&mut state, AppState::MainMenu);
+    }
+}
+
+/// Follows the game in and out of its end screens
+fn game_listener(
+    mut state: ResMut<State<AppState>>,
+    mut restarted: EventReader<GameRestarted>,
+    mut restored: EventReader<BoardRestored>,
+    mut continued: EventReader<GameContinued>,
+    mut won: EventReader<GameWon>,
+    mut game_over: EventReader<GameOver>,
+) {
+    let back_to_play =
+        restarted.iter().count() + restored.iter().count() > 0 || continued.iter().count() > 0;
+    let won = won.iter().count() > 0;
+    // Keeping going after the winning move can end the game in the same frame
+    let game_over = game_over.iter().count() > 0;
+
+    let finished = matches!(state.current(), AppState::GameOver | AppState::Won);
+    let next = if game_over {
+        AppState::GameOver
+    } else if won {
+        AppState::Won
+    } else if back_to_play && finished {
+        AppState::Playing
+    } else {
+        return;
+    };
+    go_to(&mut state, next);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{EventRegistrationPlugin, UndoRequested};
+    use crate::logic::LogicPlugin;
+    use crate::stages::StagePlugin;
+    use bevy::app::Events;
+
+    fn test_app() -> AppBuilder {
+        let mut app = App::build();
+        app.add_plugin(EventRegistrationPlugin)
+            .add_plugin(StagePlugin)
+            .add_plugin(LogicPlugin)
+            .add_plugin(AppStatePlugin {
+                start: AppState::MainMenu,
+            });
+        app.app.update();
+        app
+    }
+
+    fn send<T: Send + Sync + 'static>(app: &mut AppBuilder, event: T) {
+        app.world_mut()
+            .get_resource_mut::<Events<T>>()
+            .unwrap()
+            .send(event);
+        app.app.update();
+        app.app.update();
+    }
+
+    fn current(app: &AppBuilder) -> AppState {
+        *app.app
+            .world
+            .get_resource::<State<AppState>>()
+            .unwrap()
+            .current()
+    }
+
+    #[test]
+    fn pause_toggles_on_top_of_play() {
+        let mut app = test_app();
+        assert_eq!(current(&app), AppState::MainMenu);
+
+        send(&mut app, ContinueRequested);
+        assert_eq!(current(&app), AppState::Playing);
+
+        send(&mut app, PauseToggleRequested);
+        assert_eq!(current(&app), AppState::Paused);
+
+        send(&mut app, PauseToggleRequested);
+        assert_eq!(current(&app), AppState::Playing);
+
+        send(&mut app, PauseToggleRequested);
+        send(&mut app, MainMenuRequested);
+        assert_eq!(current(&app), AppState::MainMenu);
+    }
+
+    #[test]
+    fn game_over_is_left_by_restarting() {
+        let mut app = test_app();
+        send(&mut app, NewGameRequested);
+        assert_eq!(current(&app), AppState::Playing);
+
+        send(&mut app, GameOver);
+        assert_eq!(current(&app), AppState::GameOver);
+
+        // Nothing to undo on a fresh game, so the game over screen stays
+        send(&mut app, UndoRequested);
+        assert_eq!(current(&app), AppState::GameOver);
+
+        send(&mut app, RestartRequested);
+        assert_eq!(current(&app), AppState::Playing);
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use futures_lite::future;
+use rules::ai::{self, SearchConfig};
+
+use crate::{
+    app_state::AppState,
+    events::{AutoplayToggleRequested, MoveRequested},
+    logic::{position_map::Direction, BoardKey, LogicState},
+};
+
+/// Lets the expectimax search play, toggled on and off with a key. The search
+/// runs on a worker thread like the hint's so the frame isn't held up.
+pub struct AutoplayPlugin;
+
+impl Plugin for AutoplayPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.world_mut()
+            .get_resource_or_insert_with(SearchConfig::default);
+
+        app.insert_resource(Autoplay {
+            enabled: false,
+            search: None,
+        })
+        .add_system(toggle_listener.system())
+        .add_system_set(SystemSet::on_update(AppState::Playing).with_system(play.system()));
+    }
+}
+
+pub struct Autoplay {
+    pub enabled: bool,
+    /// The board being searched and the move it will come up with
+    search: Option<(BoardKey, Task<Option<Direction>>)>,
+}
+
+fn toggle_listener(
+    mut autoplay: ResMut<Autoplay>,
+    mut events: EventReader<AutoplayToggleRequested>,
+) {
+    for _ in events.iter() {
+        autoplay.enabled = !autoplay.enabled;
+        // Dropping the task cancels a search that is no longer wanted
+        autoplay.search = None;
+        println!("Autoplay {}", if autoplay.enabled { "on" } else { "off" });
+    }
+}
+
+/// Searches for the best move whenever the last one has finished, and
+/// requests it once found if the board is still the one searched. A won
+/// game waits for the player to choose whether to keep going.
+fn play(
+    mut autoplay: ResMut<Autoplay>,
+    config: Res<SearchConfig>,
+    state: Res<LogicState>,
+    pool: Res<AsyncComputeTaskPool>,
+    mut moves: EventWriter<MoveRequested>,
+) {
+    let can_play = autoplay.enabled && state.ready_for_next_move() && !state.is_won;
+
+    if let Some((_, task)) = autoplay.search.as_mut() {
+        if let Some(best) = future::block_on(future::poll_once(task)) {
+            let (key, _) = autoplay.search.take().unwrap();
+            if let Some(direction) = best.filter(|_| can_play && key == state.board_key()) {
+                moves.send(MoveRequested::new(direction));
+            }
+        }
+        return;
+    }
+
+    if can_play && !state.game.is_game_over() {
+        let board = state.game.board().clone();
+        let search = *config;
+        let task = pool.spawn(async move { ai::best_move(&board, &search) });
+        autoplay.search = Some((state.board_key(), task));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app_state::AppStatePlugin,
+        events::EventRegistrationPlugin,
+        logic::{rng::RngConfig, LogicPlugin},
+        stages::StagePlugin,
+    };
+    use bevy::app::{Events, ManualEventReader};
+    use bevy::tasks::TaskPool;
+
+    #[test]
+    fn the_move_is_searched_off_the_frame_and_then_requested() {
+        let mut app = App::build();
+        app.insert_resource(RngConfig { seed: Some(5) })
+            .insert_resource(AsyncComputeTaskPool(TaskPool::new()))
+            .add_plugin(EventRegistrationPlugin)
+            .add_plugin(StagePlugin)
+            .add_plugin(LogicPlugin)
+            .add_plugin(AppStatePlugin {
+                start: AppState::Playing,
+            })
+            .add_plugin(AutoplayPlugin);
+        let mut app = app.app;
+        let mut reader = ManualEventReader::<MoveRequested>::default();
+        app.update();
+        app.world
+            .get_resource_mut::<Events<AutoplayToggleRequested>>()
+            .unwrap()
+            .send(AutoplayToggleRequested);
+
+        // The frame that starts the search goes by without a move
+        while app
+            .world
+            .get_resource::<Autoplay>()
+            .unwrap()
+            .search
+            .is_none()
+        {
+            app.update();
+        }
+        let events = app.world.get_resource::<Events<MoveRequested>>().unwrap();
+        assert_eq!(reader.iter(events).count(), 0);
+
+        for _ in 0..1000 {
+            let events = app.world.get_resource::<Events<MoveRequested>>().unwrap();
+            if reader.iter(events).count() > 0 {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            app.update();
+        }
+        panic!("Autoplay never requested a move");
+    }
+}
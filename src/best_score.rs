@@ -0,0 +1,149 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+use crate::{
+    events::{BestChanged, ScoreChanged},
+    stages::CustomStage,
+    storage,
+};
+
+const FILE_NAME: &str = "best_score";
+
+pub struct BestScorePlugin;
+
+impl Plugin for BestScorePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(BestScore::load(default_path()))
+            .add_startup_system(announce_best.system())
+            .add_system_to_stage(CustomStage::After, score_changed_listener.system());
+    }
+}
+
+/// Highest score reached so far, backed by a file in the user's data directory.
+#[derive(Debug)]
+pub struct BestScore {
+    pub best: u64,
+    path: Option<PathBuf>,
+}
+
+impl BestScore {
+    /// Loads the best score from `path`. A missing or unreadable file starts from zero.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let best = match path.as_deref().map(read_best) {
+            Some(Ok(best)) => best,
+            Some(Err(error)) => {
+                println!("Could not load best score, starting from 0: {}", error);
+                0
+            }
+            None => 0,
+        };
+
+        Self { best, path }
+    }
+
+    /// Records `score`, returning true when it beats the current best.
+    pub fn update(&mut self, score: u64) -> bool {
+        if score > self.best {
+            self.best = score;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn save(&self) {
+        if let Some(path) = &self.path {
+            if let Err(error) = storage::write_atomically(path, &self.best.to_string()) {
+                println!("Could not save best score: {}", error);
+            }
+        }
+    }
+}
+
+pub fn default_path() -> Option<PathBuf> {
+    storage::data_file(FILE_NAME)
+}
+
+fn read_best(path: &Path) -> Result<u64, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(error) => return Err(error.to_string()),
+    };
+
+    contents
+        .trim()
+        .parse::<u64>()
+        .map_err(|_| format!("{:?} is not a valid score", contents.trim()))
+}
+
+fn announce_best(best_score: Res<BestScore>, mut events: EventWriter<BestChanged>) {
+    events.send(BestChanged {
+        best: best_score.best,
+    });
+}
+
+fn score_changed_listener(
+    mut best_score: ResMut<BestScore>,
+    mut events: EventReader<ScoreChanged>,
+    mut best_changed: EventWriter<BestChanged>,
+) {
+    for event in events.iter() {
+        if best_score.update(event.score) {
+            best_score.save();
+            best_changed.send(BestChanged {
+                best: best_score.best,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::test_support::TestDir;
+
+    #[test]
+    fn missing_file_starts_from_zero() {
+        let dir = TestDir::new("best-score", "missing");
+        let path = dir.file("best_score");
+        let best_score = BestScore::load(Some(path));
+        assert_eq!(best_score.best, 0);
+    }
+
+    #[test]
+    fn corrupt_file_starts_from_zero() {
+        let dir = TestDir::new("best-score", "corrupt");
+        let path = dir.file("best_score");
+        fs::write(&path, "not a number").unwrap();
+
+        assert_eq!(BestScore::load(Some(path.clone())).best, 0);
+
+        fs::write(&path, "-12").unwrap();
+        assert_eq!(BestScore::load(Some(path)).best, 0);
+    }
+
+    #[test]
+    fn saved_best_is_loaded_again() {
+        let dir = TestDir::new("best-score", "saved");
+        let path = dir.file("best_score");
+        let mut best_score = BestScore::load(Some(path.clone()));
+
+        assert!(best_score.update(128));
+        best_score.save();
+
+        assert_eq!(BestScore::load(Some(path)).best, 128);
+    }
+
+    #[test]
+    fn only_higher_scores_replace_best() {
+        let mut best_score = BestScore::load(None);
+
+        assert!(best_score.update(64));
+        assert!(!best_score.update(32));
+        assert!(!best_score.update(64));
+        assert_eq!(best_score.best, 64);
+    }
+}
@@ -0,0 +1,465 @@
+//! Plays many games without a window to compare strategies, e.g.
+//! `simulate --strategy expectimax --games 200 --threads 8 --json runs.json`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rules::ai::SearchConfig;
+use rules::history::UndoConfig;
+use rules::rng::RngConfig;
+use rules::strategy::{self, CornerStrategy, ExpectimaxStrategy, RandomStrategy, Strategy};
+use rules::{BoardConfig, Game, GameConfig};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy)]
+enum StrategyKind {
+    Random,
+    Corner,
+    Expectimax,
+}
+
+impl StrategyKind {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value {
+            "random" => Ok(StrategyKind::Random),
+            "corner" => Ok(StrategyKind::Corner),
+            "expectimax" => Ok(StrategyKind::Expectimax),
+            _ => Err(format!(
+                "Unknown strategy {}, expected random, corner or expectimax",
+                value
+            )),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            StrategyKind::Random => "random",
+            StrategyKind::Corner => "corner",
+            StrategyKind::Expectimax => "expectimax",
+        }
+    }
+
+    fn build(&self, seed: u64, search: SearchConfig) -> Box<dyn Strategy> {
+        match self {
+            StrategyKind::Random => Box::new(RandomStrategy::new(seed)),
+            StrategyKind::Corner => Box::new(CornerStrategy),
+            StrategyKind::Expectimax => Box::new(ExpectimaxStrategy { config: search }),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    games: u64,
+    seed: u64,
+    threads: usize,
+    strategy: StrategyKind,
+    board: BoardConfig,
+    search: SearchConfig,
+    csv: Option<String>,
+    json: Option<String>,
+}
+
+impl Options {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            games: 100,
+            seed: 0,
+            threads: thread::available_parallelism()
+                .map(|threads| threads.get())
+                .unwrap_or(1),
+            strategy: StrategyKind::Corner,
+            board: BoardConfig::default(),
+            // No time budget so that runs can be reproduced
+            search: SearchConfig {
+                depth: 2,
+                time_budget: None,
+            },
+            csv: None,
+            json: None,
+        };
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("{} expects a value", arg))
+            };
+            match arg.as_str() {
+                "--games" => options.games = parse_number(&arg, &value()?)?,
+                "--seed" => options.seed = parse_number(&arg, &value()?)?,
+                "--threads" => {
+                    options.threads = parse_number(&arg, &value()?)?;
+                    if options.threads == 0 {
+                        return Err("--threads must be at least 1".to_string());
+                    }
+                }
+                "--strategy" => options.strategy = StrategyKind::parse(&value()?)?,
+                "--board" => options.board = value()?.parse()?,
+                "--depth" => {
+                    options.search.depth = parse_number(&arg, &value()?)?;
+                    if options.search.depth == 0 {
+                        return Err("--depth must be at least 1".to_string());
+                    }
+                }
+                "--time" => {
+                    let millis = parse_number(&arg, &value()?)?;
+                    options.search.time_budget = Some(Duration::from_millis(millis));
+                }
+                "--csv" => options.csv = Some(value()?),
+                "--json" => options.json = Some(value()?),
+                _ => return Err(format!("Unknown argument {}", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value {} for {}", value, name))
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct GameResult {
+    seed: u64,
+    score: u64,
+    highest_tile: u64,
+    moves: u32,
+}
+
+fn play(options: &Options, seed: u64) -> GameResult {
+    let mut game = Game::new(GameConfig {
+        board: options.board,
+        undo: UndoConfig::disabled(),
+        rng: RngConfig { seed: Some(seed) },
+        ..GameConfig::default()
+    });
+    let mut strategy = options.strategy.build(seed, options.search);
+    let moves = strategy::play_out(&mut game, strategy.as_mut());
+
+    let highest_tile = game
+        .board()
+        .blocks()
+        .iter()
+        .map(|(_, number, _)| number.value())
+        .max()
+        .unwrap_or(0);
+
+    GameResult {
+        seed: seed,
+        score: game.score(),
+        highest_tile: highest_tile,
+        moves: moves,
+    }
+}
+
+/// Plays every game, handing seeds out to the threads one at a time.
+/// Results come back sorted by seed whatever the thread count.
+fn run(options: &Options) -> Vec<GameResult> {
+    let next = AtomicU64::new(0);
+    let results = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..options.threads {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                if index >= options.games {
+                    break;
+                }
+                let result = play(options, options.seed.wrapping_add(index));
+                results.lock().unwrap().push(result);
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|result| result.seed);
+    results
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct Percentiles {
+    min: u64,
+    p10: u64,
+    median: u64,
+    p90: u64,
+    max: u64,
+    mean: f64,
+}
+
+impl Percentiles {
+    /// All zero when there are no values
+    fn of(values: impl Iterator<Item = u64>) -> Self {
+        let mut values: Vec<u64> = values.collect();
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_unstable();
+        let at = |fraction: f64| {
+            let index = ((values.len() - 1) as f64 * fraction).round() as usize;
+            values[index]
+        };
+
+        Self {
+            min: at(0.0),
+            p10: at(0.1),
+            median: at(0.5),
+            p90: at(0.9),
+            max: at(1.0),
+            mean: values.iter().sum::<u64>() as f64 / values.len() as f64,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Summary {
+    strategy: &'static str,
+    board: String,
+    games: u64,
+    first_seed: u64,
+    threads: usize,
+    seconds: f64,
+    games_per_second: f64,
+    score: Percentiles,
+    moves: Percentiles,
+    /// How many games ended with each highest tile
+    highest_tiles: BTreeMap<u64, u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Report<'a> {
+    summary: &'a Summary,
+    games: &'a [GameResult],
+}
+
+fn summarize(options: &Options, results: &[GameResult], elapsed: Duration) -> Summary {
+    let mut highest_tiles = BTreeMap::new();
+    for result in results.iter() {
+        *highest_tiles.entry(result.highest_tile).or_insert(0) += 1;
+    }
+    let seconds = elapsed.as_secs_f64();
+
+    Summary {
+        strategy: options.strategy.name(),
+        board: format!("{}x{}", options.board.width, options.board.height),
+        games: options.games,
+        first_seed: options.seed,
+        threads: options.threads,
+        seconds: seconds,
+        games_per_second: options.games as f64 / seconds,
+        score: Percentiles::of(results.iter().map(|result| result.score)),
+        moves: Percentiles::of(results.iter().map(|result| result.moves as u64)),
+        highest_tiles: highest_tiles,
+    }
+}
+
+fn print_table(summary: &Summary) {
+    println!(
+        "{} games of {} on {} (seeds {}..{}), {} threads",
+        summary.games,
+        summary.strategy,
+        summary.board,
+        summary.first_seed,
+        summary.first_seed.wrapping_add(summary.games),
+        summary.threads
+    );
+    println!(
+        "{:.2}s, {:.1} games per second",
+        summary.seconds, summary.games_per_second
+    );
+    println!();
+    println!(
+        "{:<8}{:>10}{:>10}{:>10}{:>10}{:>10}{:>12}",
+        "", "min", "p10", "median", "p90", "max", "mean"
+    );
+    for (name, values) in [("score", &summary.score), ("moves", &summary.moves)] {
+        println!(
+            "{:<8}{:>10}{:>10}{:>10}{:>10}{:>10}{:>12.1}",
+            name, values.min, values.p10, values.median, values.p90, values.max, values.mean
+        );
+    }
+    println!();
+    println!(
+        "{:<14}{:>8}{:>9}{:>10}",
+        "highest tile", "games", "share", "reached"
+    );
+    let mut reached = summary.games;
+    for (tile, count) in summary.highest_tiles.iter() {
+        println!(
+            "{:<14}{:>8}{:>8.1}%{:>9.1}%",
+            tile,
+            count,
+            100.0 * *count as f64 / summary.games as f64,
+            100.0 * reached as f64 / summary.games as f64
+        );
+        reached -= count;
+    }
+}
+
+fn to_csv(results: &[GameResult]) -> String {
+    let mut csv = String::from("seed,score,highest_tile,moves\n");
+    for result in results.iter() {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            result.seed, result.score, result.highest_tile, result.moves
+        ));
+    }
+    csv
+}
+
+fn to_json(summary: &Summary, results: &[GameResult]) -> String {
+    let report = Report {
+        summary: summary,
+        games: results,
+    };
+    serde_json::to_string_pretty(&report).unwrap()
+}
+
+fn write(path: &str, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|error| format!("Could not write {}: {}", path, error))
+}
+
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(2);
+        }
+    };
+    if options.games == 0 {
+        eprintln!("Nothing to do with --games 0");
+        std::process::exit(2);
+    }
+
+    let start = Instant::now();
+    let results = run(&options);
+    let summary = summarize(&options, &results, start.elapsed());
+
+    print_table(&summary);
+
+    let mut outputs = Vec::new();
+    if let Some(path) = options.csv.as_ref() {
+        outputs.push((path, to_csv(&results)));
+    }
+    if let Some(path) = options.json.as_ref() {
+        outputs.push((path, to_json(&summary, &results)));
+    }
+    for (path, contents) in outputs {
+        if let Err(error) = write(path, &contents) {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn result(seed: u64, score: u64, highest_tile: u64, moves: u32) -> GameResult {
+        GameResult {
+            seed: seed,
+            score: score,
+            highest_tile: highest_tile,
+            moves: moves,
+        }
+    }
+
+    #[test]
+    fn arguments_are_parsed_and_checked() {
+        let options = parse(&[
+            "--games",
+            "20",
+            "--seed",
+            "7",
+            "--threads",
+            "3",
+            "--strategy",
+            "expectimax",
+            "--board",
+            "5x4",
+            "--depth",
+            "3",
+            "--time",
+            "50",
+            "--csv",
+            "out.csv",
+        ])
+        .unwrap();
+
+        assert_eq!(options.games, 20);
+        assert_eq!(options.seed, 7);
+        assert_eq!(options.threads, 3);
+        assert_eq!(options.strategy.name(), "expectimax");
+        assert_eq!(options.board, BoardConfig::new(5, 4).unwrap());
+        assert_eq!(options.search.depth, 3);
+        assert_eq!(options.search.time_budget, Some(Duration::from_millis(50)));
+        assert_eq!(options.csv.as_deref(), Some("out.csv"));
+        assert_eq!(options.json, None);
+
+        assert!(parse(&["--threads", "0"]).is_err());
+        assert!(parse(&["--depth", "0"]).is_err());
+        assert!(parse(&["--board", "1x4"]).is_err());
+        assert!(parse(&["--strategy", "greedy"]).is_err());
+        assert!(parse(&["--games"]).is_err());
+        assert!(parse(&["--games", "many"]).is_err());
+        assert!(parse(&["--fast"]).is_err());
+    }
+
+    #[test]
+    fn percentiles_of_few_values() {
+        assert_eq!(Percentiles::of(std::iter::empty()), Percentiles::default());
+        assert_eq!(
+            Percentiles::of(std::iter::once(12)),
+            Percentiles {
+                min: 12,
+                p10: 12,
+                median: 12,
+                p90: 12,
+                max: 12,
+                mean: 12.0,
+            }
+        );
+
+        let percentiles = Percentiles::of((1..=11).rev());
+        assert_eq!(percentiles.min, 1);
+        assert_eq!(percentiles.p10, 2);
+        assert_eq!(percentiles.median, 6);
+        assert_eq!(percentiles.p90, 10);
+        assert_eq!(percentiles.max, 11);
+        assert_eq!(percentiles.mean, 6.0);
+    }
+
+    #[test]
+    fn results_are_written_as_csv_and_json() {
+        let results = vec![result(0, 1200, 128, 140), result(1, 2500, 256, 230)];
+        assert_eq!(
+            to_csv(&results),
+            "seed,score,highest_tile,moves\n0,1200,128,140\n1,2500,256,230\n"
+        );
+
+        let options = parse(&["--games", "2", "--threads", "1"]).unwrap();
+        let summary = summarize(&options, &results, Duration::from_secs(2));
+        let json: serde_json::Value = serde_json::from_str(&to_json(&summary, &results)).unwrap();
+
+        assert_eq!(json["summary"]["games"], 2);
+        assert_eq!(json["summary"]["board"], "4x4");
+        assert_eq!(json["summary"]["games_per_second"], 1.0);
+        assert_eq!(json["summary"]["score"]["max"], 2500);
+        assert_eq!(json["summary"]["highest_tiles"]["256"], 1);
+        assert_eq!(json["games"][1]["moves"], 230);
+        assert_eq!(json["games"].as_array().unwrap().len(), 2);
+    }
+}
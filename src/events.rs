@@ -1,39 +1,86 @@
+use crate::layout::Layout;
 use crate::logic::{number::Number, position_map::Direction, position_map::Position};
 use bevy::prelude::*;
+use rules::ai::MoveScore;
 
 pub struct EventRegistrationPlugin;
 
 impl Plugin for EventRegistrationPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_event::<ScoreChanged>()
+            .add_event::<ScoreGained>()
             .add_event::<BestChanged>()
             .add_event::<BlockAdded>()
             .add_event::<MoveRequested>()
             .add_event::<BlocksMoved>()
             .add_event::<BlocksDeleted>()
             .add_event::<AnimationCompleted>()
+            .add_event::<AnimationSkipRequested>()
             .add_event::<RestartRequested>()
             .add_event::<GameRestarted>()
-            .add_event::<GameOver>();
+            .add_event::<UndoRequested>()
+            .add_event::<BoardRestored>()
+            .add_event::<ResumeOffered>()
+            .add_event::<ResumeRequested>()
+            .add_event::<ResumeDeclined>()
+            .add_event::<ResumeClosed>()
+            .add_event::<GameOver>()
+            .add_event::<GameWon>()
+            .add_event::<KeepGoingRequested>()
+            .add_event::<GameContinued>()
+            .add_event::<AutoplayToggleRequested>()
+            .add_event::<HintRequested>()
+            .add_event::<HintShown>()
+            .add_event::<ReplaySaveRequested>()
+            .add_event::<NewGameRequested>()
+            .add_event::<ContinueRequested>()
+            .add_event::<PauseToggleRequested>()
+            .add_event::<MainMenuRequested>()
+            .add_event::<ThemeSwitchRequested>()
+            .add_event::<LayoutChanged>();
     }
 }
 
 pub struct ScoreChanged {
-    pub score: i32,
+    pub score: u64,
+}
+
+/// Points made by the merges of one move
+pub struct ScoreGained {
+    pub points: u64,
 }
 
 pub struct BestChanged {
-    pub best: i32,
+    pub best: u64,
+}
+
+/// Where a block on the board came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockOrigin {
+    /// Already on the board, e.g. at the start or after an undo
+    Placed,
+    /// The random block added after a move
+    Spawned,
+    Merged,
 }
 
 pub struct BlockAdded {
     pub id: i32,
     pub number: Number,
     pub position: Position,
+    pub origin: BlockOrigin,
 }
 
 pub struct GameOver;
 
+/// A merge made the win target for the first time this game
+pub struct GameWon;
+
+pub struct KeepGoingRequested;
+
+/// Play carries on after the game was won
+pub struct GameContinued;
+
 pub struct MoveRequested {
     pub direction: Direction,
 }
@@ -56,5 +103,52 @@ pub struct BlocksDeleted {
 
 pub struct AnimationCompleted;
 
+/// A move was queued behind the running animation, which should finish at once
+pub struct AnimationSkipRequested;
+
 pub struct RestartRequested;
 pub struct GameRestarted;
+
+pub struct UndoRequested;
+
+/// The board was replaced as a whole, a `BlockAdded` follows for every block on it
+pub struct BoardRestored;
+
+/// A game from a previous session can be resumed
+pub struct ResumeOffered {
+    pub score: u64,
+}
+
+pub struct ResumeRequested;
+pub struct ResumeDeclined;
+
+/// The player answered the resume offer, or ignored it by playing on
+pub struct ResumeClosed;
+
+/// Switches between playing by hand and letting the AI play
+pub struct AutoplayToggleRequested;
+
+pub struct HintRequested;
+
+/// Result of a hint search, a score for every legal move
+pub struct HintShown {
+    pub scores: Vec<MoveScore>,
+}
+
+/// Writes the game so far to a replay file
+pub struct ReplaySaveRequested;
+
+/// Drops the current game, and any game offered for resuming, for a new one
+pub struct NewGameRequested;
+/// Goes back to the game in progress from a menu
+pub struct ContinueRequested;
+pub struct PauseToggleRequested;
+pub struct MainMenuRequested;
+
+/// Repaints the game with the next theme
+pub struct ThemeSwitchRequested;
+
+/// The window was resized and `Layout` recomputed, nodes placed for `previous` have to move
+pub struct LayoutChanged {
+    pub previous: Layout,
+}
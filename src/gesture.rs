@@ -0,0 +1,243 @@
+use bevy::input::touch::Touches;
+use bevy::prelude::*;
+
+use crate::app_state::AppState;
+use crate::events::MoveRequested;
+use crate::logic::position_map::Direction;
+
+/// Turns mouse drags and touch swipes into moves
+pub struct GesturePlugin;
+
+impl Plugin for GesturePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.world_mut()
+            .get_resource_or_insert_with(GestureConfig::default);
+
+        app.insert_resource(Gestures {
+            mouse: None,
+            touch: None,
+        })
+        .add_system_set(
+            SystemSet::on_update(AppState::Playing)
+                .with_system(mouse_drag.system())
+                .with_system(touch_swipe.system()),
+        );
+    }
+}
+
+/// How far and how straight a pointer has to travel to count as a move
+#[derive(Debug, Clone, Copy)]
+pub struct GestureConfig {
+    /// Logical pixels
+    pub min_distance: f32,
+    /// Largest angle in degrees between the drag and the axis of its move
+    pub angle_tolerance: f32,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            min_distance: 40.0,
+            angle_tolerance: 30.0,
+        }
+    }
+}
+
+impl GestureConfig {
+    /// The move for a pointer that went `delta` from where it was pressed,
+    /// with y pointing up
+    pub fn direction(&self, delta: Vec2) -> Option<Direction> {
+        let distance = delta.length();
+        if distance < self.min_distance {
+            return None;
+        }
+
+        let (direction, along) = if delta.x.abs() >= delta.y.abs() {
+            let direction = if delta.x > 0.0 {
+                Direction::RIGHT
+            } else {
+                Direction::LEFT
+            };
+            (direction, delta.x.abs())
+        } else {
+            let direction = if delta.y > 0.0 {
+                Direction::TOP
+            } else {
+                Direction::BOTTOM
+            };
+            (direction, delta.y.abs())
+        };
+
+        let angle = (along / distance).min(1.0).acos().to_degrees();
+        Some(direction).filter(|_| angle <= self.angle_tolerance)
+    }
+}
+
+/// A pointer held down, it makes at most one move however long it is dragged
+#[derive(Debug, Clone, Copy)]
+pub struct Drag {
+    start: Vec2,
+    done: bool,
+}
+
+impl Drag {
+    pub fn new(start: Vec2) -> Self {
+        Self {
+            start: start,
+            done: false,
+        }
+    }
+
+    /// The move as soon as the pointer has gone far and straight enough.
+    /// A drag that starts off diagonally can still straighten out.
+    pub fn moved_to(&mut self, position: Vec2, config: &GestureConfig) -> Option<Direction> {
+        if self.done {
+            return None;
+        }
+
+        let direction = config.direction(position - self.start);
+        self.done = direction.is_some();
+        direction
+    }
+}
+
+struct Gestures {
+    mouse: Option<Drag>,
+    /// The first finger down, other fingers are ignored until it is lifted
+    touch: Option<(u64, Drag)>,
+}
+
+fn mouse_drag(
+    config: Res<GestureConfig>,
+    mut gestures: ResMut<Gestures>,
+    buttons: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    mut moves: EventWriter<MoveRequested>,
+) {
+    let cursor = windows
+        .get_primary()
+        .and_then(|window| window.cursor_position());
+
+    if buttons.just_pressed(MouseButton::Left) {
+        gestures.mouse = cursor.map(Drag::new);
+    }
+
+    if let (Some(drag), Some(cursor)) = (gestures.mouse.as_mut(), cursor) {
+        if let Some(direction) = drag.moved_to(cursor, &config) {
+            moves.send(MoveRequested::new(direction));
+        }
+    }
+
+    if buttons.just_released(MouseButton::Left) {
+        gestures.mouse = None;
+    }
+}
+
+/// Bevy only counts touch positions from the bottom on mobile, on a desktop
+/// they come from the top like winit's
+fn touch_point(position: Vec2) -> Vec2 {
+    if cfg!(target_os = "android") || cfg!(target_os = "ios") {
+        position
+    } else {
+        Vec2::new(position.x, -position.y)
+    }
+}
+
+fn touch_swipe(
+    config: Res<GestureConfig>,
+    mut gestures: ResMut<Gestures>,
+    touches: Res<Touches>,
+    mut moves: EventWriter<MoveRequested>,
+) {
+    if gestures.touch.is_none() {
+        gestures.touch = touches
+            .iter_just_pressed()
+            .next()
+            .map(|touch| (touch.id(), Drag::new(touch_point(touch.start_position()))));
+    }
+
+    let (id, drag) = match gestures.touch.as_mut() {
+        Some(touch) => touch,
+        None => return,
+    };
+    let position = touches
+        .get_pressed(*id)
+        .or_else(|| touches.get_released(*id))
+        .map(|touch| touch_point(touch.position()));
+    if let Some(direction) = position.and_then(|position| drag.moved_to(position, &config)) {
+        moves.send(MoveRequested::new(direction));
+    }
+
+    if touches.just_released(*id) || touches.just_cancelled(*id) {
+        gestures.touch = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds a pointer path to a drag, returning every move it made
+    fn drag(path: &[(f32, f32)]) -> Vec<Direction> {
+        let config = GestureConfig::default();
+        let mut drag = Drag::new(Vec2::new(path[0].0, path[0].1));
+        path[1..]
+            .iter()
+            .filter_map(|(x, y)| drag.moved_to(Vec2::new(*x, *y), &config))
+            .collect()
+    }
+
+    #[test]
+    fn straight_drags_move_along_their_axis() {
+        assert_eq!(
+            drag(&[(0.0, 0.0), (20.0, 0.0), (60.0, 5.0)]),
+            vec![Direction::RIGHT]
+        );
+        assert_eq!(
+            drag(&[(100.0, 100.0), (50.0, 110.0)]),
+            vec![Direction::LEFT]
+        );
+        assert_eq!(drag(&[(0.0, 0.0), (0.0, 50.0)]), vec![Direction::TOP]);
+        assert_eq!(drag(&[(0.0, 0.0), (-10.0, -45.0)]), vec![Direction::BOTTOM]);
+    }
+
+    #[test]
+    fn short_drags_make_no_move() {
+        assert!(drag(&[(0.0, 0.0), (10.0, 0.0), (30.0, 10.0), (0.0, 0.0)]).is_empty());
+    }
+
+    #[test]
+    fn diagonal_drags_wait_until_they_straighten_out() {
+        assert!(drag(&[(0.0, 0.0), (50.0, 50.0)]).is_empty());
+        assert_eq!(
+            drag(&[(0.0, 0.0), (50.0, 50.0), (150.0, 50.0)]),
+            vec![Direction::RIGHT]
+        );
+    }
+
+    #[test]
+    fn a_drag_makes_one_move_however_far_it_goes() {
+        assert_eq!(
+            drag(&[(0.0, 0.0), (50.0, 0.0), (150.0, 0.0), (150.0, 150.0)]),
+            vec![Direction::RIGHT]
+        );
+    }
+
+    #[test]
+    fn angle_tolerance_is_configurable() {
+        let strict = GestureConfig {
+            min_distance: 10.0,
+            angle_tolerance: 5.0,
+        };
+
+        assert_eq!(
+            strict.direction(Vec2::new(100.0, 5.0)),
+            Some(Direction::RIGHT)
+        );
+        assert_eq!(strict.direction(Vec2::new(100.0, 20.0)), None);
+        assert_eq!(
+            GestureConfig::default().direction(Vec2::new(100.0, 20.0)),
+            Some(Direction::RIGHT)
+        );
+    }
+}
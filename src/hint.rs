@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use bevy::tasks::{AsyncComputeTaskPool, Task};
+use futures_lite::future;
+use rules::ai::{self, MoveScore, SearchConfig};
+use std::time::Duration;
+
+use crate::{
+    events::{HintRequested, HintShown},
+    logic::{BoardKey, LogicState},
+};
+
+/// Suggests the best next move, searching on a worker thread so the
+/// frame isn't held up.
+pub struct HintPlugin;
+
+impl Plugin for HintPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.world_mut()
+            .get_resource_or_insert_with(HintConfig::default);
+
+        app.insert_resource(Hint { search: None })
+            .add_system(hint_requested_listener.system())
+            .add_system(hint_finished.system());
+    }
+}
+
+/// Search behind a hint, kept short so the hint shows up quickly
+#[derive(Debug, Clone, Copy)]
+pub struct HintConfig {
+    pub search: SearchConfig,
+}
+
+impl Default for HintConfig {
+    fn default() -> Self {
+        Self {
+            search: SearchConfig {
+                depth: 3,
+                time_budget: Some(Duration::from_millis(150)),
+            },
+        }
+    }
+}
+
+pub struct Hint {
+    search: Option<(BoardKey, Task<Vec<MoveScore>>)>,
+}
+
+fn hint_requested_listener(
+    mut hint: ResMut<Hint>,
+    config: Res<HintConfig>,
+    state: Res<LogicState>,
+    pool: Res<AsyncComputeTaskPool>,
+    mut events: EventReader<HintRequested>,
+) {
+    for _ in events.iter() {
+        if hint.search.is_some() || !state.ready_for_next_move() || state.game.is_game_over() {
+            continue;
+        }
+
+        let board = state.game.board().clone();
+        let search = config.search;
+        let task = pool.spawn(async move { ai::evaluate(&board, &search) });
+        hint.search = Some((state.board_key(), task));
+    }
+}
+
+/// Shows the hint once the search is done, unless a move was made meanwhile
+fn hint_finished(
+    mut hint: ResMut<Hint>,
+    state: Res<LogicState>,
+    mut shown: EventWriter<HintShown>,
+) {
+    let scores = match hint.search.as_mut() {
+        Some((_, task)) => future::block_on(future::poll_once(task)),
+        None => return,
+    };
+
+    if let Some(scores) = scores {
+        let (key, _) = hint.search.take().unwrap();
+        if key == state.board_key() && !scores.is_empty() {
+            shown.send(HintShown { scores: scores });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        events::{EventRegistrationPlugin, MoveRequested},
+        logic::{position_map::Direction, rng::RngConfig, LogicPlugin},
+        stages::StagePlugin,
+    };
+    use bevy::app::{Events, ManualEventReader};
+    use bevy::tasks::TaskPool;
+
+    fn app() -> App {
+        let mut app = App::build();
+        app.insert_resource(RngConfig { seed: Some(5) })
+            .insert_resource(AsyncComputeTaskPool(TaskPool::new()))
+            .add_plugin(EventRegistrationPlugin)
+            .add_plugin(StagePlugin)
+            .add_plugin(LogicPlugin)
+            .add_plugin(HintPlugin);
+        app.app.update();
+        app.app
+    }
+
+    fn send<T: Send + Sync + 'static>(app: &mut App, event: T) {
+        app.world
+            .get_resource_mut::<Events<T>>()
+            .unwrap()
+            .send(event);
+    }
+
+    /// Updates until the search is done, returning every hint shown
+    fn finish_search(
+        app: &mut App,
+        reader: &mut ManualEventReader<HintShown>,
+    ) -> Vec<Vec<MoveScore>> {
+        let mut hints = vec![];
+        for _ in 0..1000 {
+            app.update();
+            let events = app.world.get_resource::<Events<HintShown>>().unwrap();
+            hints.extend(reader.iter(events).map(|hint| hint.scores.clone()));
+            if app.world.get_resource::<Hint>().unwrap().search.is_none() {
+                return hints;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        panic!("The hint search never finished");
+    }
+
+    #[test]
+    fn hint_scores_every_legal_move() {
+        let mut app = app();
+        let mut reader = ManualEventReader::<HintShown>::default();
+
+        send(&mut app, HintRequested);
+        let hints = finish_search(&mut app, &mut reader);
+
+        assert_eq!(hints.len(), 1);
+        let state = app.world.get_resource::<LogicState>().unwrap();
+        let legal = Direction::ALL
+            .iter()
+            .filter(|direction| state.game.can_move(**direction))
+            .count();
+        assert_eq!(hints[0].len(), legal);
+    }
+
+    #[test]
+    fn hint_for_an_old_board_is_dropped() {
+        let mut app = app();
+        let mut reader = ManualEventReader::<HintShown>::default();
+        let direction = {
+            let state = app.world.get_resource::<LogicState>().unwrap();
+            *Direction::ALL
+                .iter()
+                .find(|direction| state.game.can_move(**direction))
+                .unwrap()
+        };
+
+        send(&mut app, HintRequested);
+        send(&mut app, MoveRequested::new(direction));
+        let hints = finish_search(&mut app, &mut reader);
+
+        assert!(hints.is_empty());
+    }
+}
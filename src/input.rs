@@ -1,37 +1,388 @@
-use bevy::input::keyboard::{KeyCode, KeyboardInput};
+use std::collections::{HashMap, HashSet};
+
+use bevy::app::AppExit;
+use bevy::ecs::system::SystemParam;
+use bevy::input::gamepad::{
+    Gamepad, GamepadAxisType, GamepadButtonType, GamepadEvent, GamepadEventType,
+};
+use bevy::input::keyboard::KeyboardInput;
 use bevy::input::ElementState;
 use bevy::prelude::*;
 
-use crate::events::{MoveRequested, RestartRequested};
+use crate::app_state::AppState;
+use crate::events::{
+    AutoplayToggleRequested, ContinueRequested, HintRequested, KeepGoingRequested,
+    MainMenuRequested, MoveRequested, NewGameRequested, PauseToggleRequested, ReplaySaveRequested,
+    RestartRequested, ResumeDeclined, ResumeRequested, ThemeSwitchRequested, UndoRequested,
+};
+use crate::key_map::{Action, KeyMap};
 use crate::logic::position_map::Direction;
 
+/// How far a stick has to be pushed before it counts, from 0 to 1
+const STICK_DEADZONE: f32 = 0.5;
+/// How far an analog button has to be pressed before it counts
+const BUTTON_THRESHOLD: f32 = 0.5;
+
 pub struct InputPlugin;
 
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_system(handle_keyboard_input.system());
+        app.world_mut().get_resource_or_insert_with(KeyMap::default);
+
+        app.insert_resource(Gamepads::default())
+            .add_system(handle_keyboard_input.system())
+            .add_system(handle_gamepad_input.system());
     }
 }
 
+/// Sends the event an action stands for on the current screen
+#[derive(SystemParam)]
+pub struct ActionWriters<'a> {
+    moves: EventWriter<'a, MoveRequested>,
+    restart: EventWriter<'a, RestartRequested>,
+    undo: EventWriter<'a, UndoRequested>,
+    resume: EventWriter<'a, ResumeRequested>,
+    decline: EventWriter<'a, ResumeDeclined>,
+    keep_going: EventWriter<'a, KeepGoingRequested>,
+    autoplay: EventWriter<'a, AutoplayToggleRequested>,
+    hint: EventWriter<'a, HintRequested>,
+    replay: EventWriter<'a, ReplaySaveRequested>,
+    new_game: EventWriter<'a, NewGameRequested>,
+    continue_game: EventWriter<'a, ContinueRequested>,
+    pause: EventWriter<'a, PauseToggleRequested>,
+    main_menu: EventWriter<'a, MainMenuRequested>,
+    theme: EventWriter<'a, ThemeSwitchRequested>,
+    exit: EventWriter<'a, AppExit>,
+}
+
+impl<'a> ActionWriters<'a> {
+    fn send(&mut self, state: AppState, action: Action) {
+        use AppState::*;
+
+        match (state, action) {
+            (Playing, Action::MoveUp) => self.moves.send(MoveRequested::new(Direction::TOP)),
+            (Playing, Action::MoveDown) => self.moves.send(MoveRequested::new(Direction::BOTTOM)),
+            (Playing, Action::MoveLeft) => self.moves.send(MoveRequested::new(Direction::LEFT)),
+            (Playing, Action::MoveRight) => self.moves.send(MoveRequested::new(Direction::RIGHT)),
+            (MainMenu, Action::Confirm) | (Paused, Action::Confirm) => {
+                self.continue_game.send(ContinueRequested)
+            }
+            (MainMenu, Action::NewGame) | (Paused, Action::NewGame) => {
+                self.new_game.send(NewGameRequested)
+            }
+            (MainMenu, Action::Pause) => self.exit.send(AppExit),
+            (Playing, Action::Pause) | (Paused, Action::Pause) => {
+                self.pause.send(PauseToggleRequested)
+            }
+            (Paused, Action::MainMenu) => self.main_menu.send(MainMenuRequested),
+            (Playing, Action::Confirm) => self.resume.send(ResumeRequested),
+            (Playing, Action::NewGame) => self.decline.send(ResumeDeclined),
+            (Playing, Action::Autoplay) => self.autoplay.send(AutoplayToggleRequested),
+            (Playing, Action::Hint) => self.hint.send(HintRequested),
+            (GameOver, Action::Restart) | (Won, Action::Restart) => {
+                self.restart.send(RestartRequested)
+            }
+            (Won, Action::KeepGoing) => self.keep_going.send(KeepGoingRequested),
+            (Playing, Action::Undo) | (GameOver, Action::Undo) | (Won, Action::Undo) => {
+                self.undo.send(UndoRequested)
+            }
+            (Playing, Action::SaveReplay)
+            | (GameOver, Action::SaveReplay)
+            | (Won, Action::SaveReplay) => self.replay.send(ReplaySaveRequested),
+            (_, Action::NextTheme) => self.theme.send(ThemeSwitchRequested),
+            _ => (),
+        }
+    }
+}
+
+fn move_action(direction: Direction) -> Action {
+    match direction {
+        Direction::TOP => Action::MoveUp,
+        Direction::BOTTOM => Action::MoveDown,
+        Direction::LEFT => Action::MoveLeft,
+        Direction::RIGHT => Action::MoveRight,
+    }
+}
+
+/// What a gamepad button does on the current screen. South confirms, or
+/// restarts a finished game, east goes back or undoes, start pauses and
+/// the shoulder buttons switch themes.
+fn button_action(state: AppState, button: GamepadButtonType) -> Option<Action> {
+    use AppState::*;
+    use GamepadButtonType::*;
+
+    let action = match (state, button) {
+        (_, DPadUp) => Action::MoveUp,
+        (_, DPadDown) => Action::MoveDown,
+        (_, DPadLeft) => Action::MoveLeft,
+        (_, DPadRight) => Action::MoveRight,
+        (GameOver, South) | (Won, South) => Action::Restart,
+        (_, South) | (MainMenu, Start) => Action::Confirm,
+        (Paused, East) | (_, Start) => Action::Pause,
+        (_, East) => Action::Undo,
+        (Paused, North) => Action::MainMenu,
+        (Won, North) => Action::KeepGoing,
+        (_, North) => Action::Hint,
+        (Playing, West) => Action::Autoplay,
+        (_, West) => Action::NewGame,
+        (_, Select) => Action::SaveReplay,
+        (_, LeftTrigger) | (_, RightTrigger) => Action::NextTheme,
+        _ => return None,
+    };
+    Some(action)
+}
+
+/// Keys mean different things on each screen. A single system reads them so
+/// that a key changing the screen isn't read again by the next one.
 fn handle_keyboard_input(
+    state: Res<State<AppState>>,
+    key_map: Res<KeyMap>,
     mut input_events: EventReader<KeyboardInput>,
-    mut move_events: EventWriter<MoveRequested>,
-    mut restart_events: EventWriter<RestartRequested>,
+    mut actions: ActionWriters,
 ) {
-    use KeyCode::*;
-
     for event in input_events.iter() {
-        if let Some(keycode) = event.key_code {
-            if event.state == ElementState::Pressed {
-                match keycode {
-                    Up => move_events.send(MoveRequested::new(Direction::TOP)),
-                    Down => move_events.send(MoveRequested::new(Direction::BOTTOM)),
-                    Left => move_events.send(MoveRequested::new(Direction::LEFT)),
-                    Right => move_events.send(MoveRequested::new(Direction::RIGHT)),
-                    Space => restart_events.send(RestartRequested),
-                    _ => (),
+        let keycode = match event.key_code {
+            Some(keycode) if event.state == ElementState::Pressed => keycode,
+            _ => continue,
+        };
+
+        if let Some(action) = key_map.action(keycode) {
+            actions.send(*state.current(), action);
+        }
+    }
+}
+
+/// A stick, or a D-pad reported as axes, that moves once each time it is
+/// pushed in a direction rather than on every frame it is held there
+#[derive(Debug, Default)]
+struct Stick {
+    position: Vec2,
+    held: Option<Direction>,
+}
+
+impl Stick {
+    /// The direction the stick was just pushed in, if any
+    fn update(&mut self) -> Option<Direction> {
+        let direction = stick_direction(self.position);
+        let pushed = direction.filter(|direction| self.held != Some(*direction));
+        self.held = direction;
+        pushed
+    }
+}
+
+fn stick_direction(position: Vec2) -> Option<Direction> {
+    if position.x.abs() < STICK_DEADZONE && position.y.abs() < STICK_DEADZONE {
+        return None;
+    }
+
+    let direction = if position.x.abs() >= position.y.abs() {
+        if position.x > 0.0 {
+            Direction::RIGHT
+        } else {
+            Direction::LEFT
+        }
+    } else if position.y > 0.0 {
+        Direction::TOP
+    } else {
+        Direction::BOTTOM
+    };
+    Some(direction)
+}
+
+#[derive(Debug, Default)]
+struct Pad {
+    left_stick: Stick,
+    dpad: Stick,
+    pressed: HashSet<GamepadButtonType>,
+}
+
+/// Controllers plugged in, so that they can come and go while playing
+#[derive(Default)]
+struct Gamepads {
+    pads: HashMap<Gamepad, Pad>,
+}
+
+fn handle_gamepad_input(
+    state: Res<State<AppState>>,
+    mut gamepads: ResMut<Gamepads>,
+    mut gamepad_events: EventReader<GamepadEvent>,
+    mut actions: ActionWriters,
+) {
+    for GamepadEvent(gamepad, event) in gamepad_events.iter() {
+        match event {
+            GamepadEventType::Connected => {
+                println!("Gamepad {} connected", gamepad.0);
+                gamepads.pads.insert(*gamepad, Pad::default());
+            }
+            GamepadEventType::Disconnected => {
+                println!("Gamepad {} disconnected", gamepad.0);
+                gamepads.pads.remove(gamepad);
+            }
+            GamepadEventType::ButtonChanged(button, value) => {
+                let pad = gamepads.pads.entry(*gamepad).or_default();
+                if *value < BUTTON_THRESHOLD {
+                    pad.pressed.remove(button);
+                } else if pad.pressed.insert(*button) {
+                    if let Some(action) = button_action(*state.current(), *button) {
+                        actions.send(*state.current(), action);
+                    }
+                }
+            }
+            GamepadEventType::AxisChanged(axis, value) => {
+                let pad = gamepads.pads.entry(*gamepad).or_default();
+                let stick = match axis {
+                    GamepadAxisType::LeftStickX | GamepadAxisType::LeftStickY => {
+                        &mut pad.left_stick
+                    }
+                    GamepadAxisType::DPadX | GamepadAxisType::DPadY => &mut pad.dpad,
+                    _ => continue,
+                };
+                match axis {
+                    GamepadAxisType::LeftStickX | GamepadAxisType::DPadX => {
+                        stick.position.x = *value
+                    }
+                    _ => stick.position.y = *value,
+                }
+
+                if let Some(direction) = stick.update() {
+                    actions.send(*state.current(), move_action(direction));
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::AppStatePlugin;
+    use crate::events::EventRegistrationPlugin;
+    use bevy::app::{Events, ManualEventReader};
+
+    fn test_app() -> (AppBuilder, ManualEventReader<MoveRequested>) {
+        let mut app = App::build();
+        app.add_event::<KeyboardInput>()
+            .add_event::<GamepadEvent>()
+            .add_event::<AppExit>()
+            .add_plugin(EventRegistrationPlugin)
+            .add_plugin(AppStatePlugin {
+                start: AppState::Playing,
+            })
+            .add_plugin(InputPlugin);
+        app.app.update();
+        let reader = app
+            .world_mut()
+            .get_resource_mut::<Events<MoveRequested>>()
+            .unwrap()
+            .get_reader();
+        (app, reader)
+    }
+
+    /// Sends one gamepad event per frame, returning the moves they made
+    fn feed(
+        app: &mut AppBuilder,
+        reader: &mut ManualEventReader<MoveRequested>,
+        events: &[GamepadEventType],
+    ) -> Vec<Direction> {
+        let mut moves = vec![];
+        for event in events.iter() {
+            app.world_mut()
+                .get_resource_mut::<Events<GamepadEvent>>()
+                .unwrap()
+                .send(GamepadEvent(Gamepad(0), event.clone()));
+            app.app.update();
+
+            let events = app
+                .app
+                .world
+                .get_resource::<Events<MoveRequested>>()
+                .unwrap();
+            moves.extend(reader.iter(events).map(|event| event.direction));
+        }
+        moves
+    }
+
+    fn stick_x(value: f32) -> GamepadEventType {
+        GamepadEventType::AxisChanged(GamepadAxisType::LeftStickX, value)
+    }
+
+    #[test]
+    fn holding_the_stick_moves_once() {
+        let (mut app, mut reader) = test_app();
+
+        let moves = feed(
+            &mut app,
+            &mut reader,
+            &[
+                GamepadEventType::Connected,
+                stick_x(0.3),
+                stick_x(0.8),
+                stick_x(0.9),
+                stick_x(1.0),
+                stick_x(0.1),
+                stick_x(-0.7),
+                GamepadEventType::AxisChanged(GamepadAxisType::LeftStickY, 0.9),
+            ],
+        );
+
+        assert_eq!(
+            moves,
+            vec![Direction::RIGHT, Direction::LEFT, Direction::TOP]
+        );
+    }
+
+    #[test]
+    fn dpad_buttons_move_on_press_only() {
+        let (mut app, mut reader) = test_app();
+        let dpad_right =
+            |value| GamepadEventType::ButtonChanged(GamepadButtonType::DPadRight, value);
+
+        let moves = feed(
+            &mut app,
+            &mut reader,
+            &[
+                dpad_right(1.0),
+                dpad_right(1.0),
+                dpad_right(0.0),
+                dpad_right(1.0),
+            ],
+        );
+
+        assert_eq!(moves, vec![Direction::RIGHT, Direction::RIGHT]);
+    }
+
+    #[test]
+    fn reconnected_gamepad_starts_from_rest() {
+        let (mut app, mut reader) = test_app();
+
+        let moves = feed(
+            &mut app,
+            &mut reader,
+            &[
+                GamepadEventType::Connected,
+                stick_x(1.0),
+                GamepadEventType::Disconnected,
+                GamepadEventType::Connected,
+                stick_x(1.0),
+            ],
+        );
+
+        assert_eq!(moves, vec![Direction::RIGHT, Direction::RIGHT]);
+    }
+
+    #[test]
+    fn face_buttons_depend_on_the_screen() {
+        use GamepadButtonType::*;
+
+        assert_eq!(
+            button_action(AppState::GameOver, South),
+            Some(Action::Restart)
+        );
+        assert_eq!(
+            button_action(AppState::MainMenu, South),
+            Some(Action::Confirm)
+        );
+        assert_eq!(button_action(AppState::Playing, East), Some(Action::Undo));
+        assert_eq!(button_action(AppState::Paused, East), Some(Action::Pause));
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use bevy::input::keyboard::KeyCode;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::storage;
+
+const FILE_NAME: &str = "keys.json";
+/// Seconds between two looks at the key map file
+const RELOAD_INTERVAL: f32 = 1.0;
+
+/// Loads the key map from the user's data directory, and again whenever it changes
+pub struct KeyMapPlugin;
+
+impl Plugin for KeyMapPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let mut watcher = KeyMapWatcher::new(storage::data_file(FILE_NAME));
+        let key_map = match watcher.changed() {
+            Some(Ok(key_map)) => key_map,
+            Some(Err(error)) => {
+                println!("Could not load key map, using the arrow keys: {}", error);
+                KeyMap::default()
+            }
+            None => KeyMap::default(),
+        };
+        key_map.report_conflicts();
+
+        app.insert_resource(key_map)
+            .insert_resource(watcher)
+            .add_system(reload.system());
+    }
+}
+
+/// Something the player can ask for, what it does depends on the screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    /// Starts a new game once this one is over or won
+    Restart,
+    Undo,
+    /// Pauses while playing, quits from the main menu
+    Pause,
+    /// Continues from a menu, or resumes the saved game
+    Confirm,
+    /// Starts a new game from a menu, or declines the saved game
+    NewGame,
+    MainMenu,
+    KeepGoing,
+    Autoplay,
+    Hint,
+    SaveReplay,
+    NextTheme,
+}
+
+/// Sets of bindings to start from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset {
+    Arrows,
+    /// WASD moves as well as the arrows, autoplay moves to P
+    Wasd,
+    /// Vim's hjkl moves as well as the arrows, keep going moves to C and hint to /
+    Hjkl,
+}
+
+/// Keys bound to every action, an action may have several
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyMap {
+    bindings: BTreeMap<Action, Vec<KeyCode>>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::preset(Preset::Arrows)
+    }
+}
+
+impl KeyMap {
+    pub fn preset(preset: Preset) -> Self {
+        use KeyCode::*;
+
+        let mut bindings: BTreeMap<Action, Vec<KeyCode>> = vec![
+            (Action::MoveUp, vec![Up]),
+            (Action::MoveDown, vec![Down]),
+            (Action::MoveLeft, vec![Left]),
+            (Action::MoveRight, vec![Right]),
+            (Action::Restart, vec![Space]),
+            (Action::Undo, vec![U]),
+            (Action::Pause, vec![Escape]),
+            (Action::Confirm, vec![Return]),
+            (Action::NewGame, vec![N]),
+            (Action::MainMenu, vec![M]),
+            (Action::KeepGoing, vec![K]),
+            (Action::Autoplay, vec![A]),
+            (Action::Hint, vec![H]),
+            (Action::SaveReplay, vec![R]),
+            (Action::NextTheme, vec![T]),
+        ]
+        .into_iter()
+        .collect();
+
+        let moves = match preset {
+            Preset::Arrows => return Self { bindings: bindings },
+            Preset::Wasd => {
+                bindings.insert(Action::Autoplay, vec![P]);
+                [W, S, A, D]
+            }
+            Preset::Hjkl => {
+                bindings.insert(Action::KeepGoing, vec![C]);
+                bindings.insert(Action::Hint, vec![Slash]);
+                [K, J, H, L]
+            }
+        };
+        for (action, key) in [
+            Action::MoveUp,
+            Action::MoveDown,
+            Action::MoveLeft,
+            Action::MoveRight,
+        ]
+        .iter()
+        .zip(moves)
+        {
+            bindings.get_mut(action).unwrap().push(key);
+        }
+
+        Self { bindings: bindings }
+    }
+
+    /// Reads a key map such as `{"preset": "wasd", "bindings": {"undo": ["Z", "U"]}}`,
+    /// where the listed actions replace the keys of the preset
+    pub fn from_json(contents: &str) -> Result<Self, String> {
+        let file: KeyMapFile = serde_json::from_str(contents).map_err(|error| error.to_string())?;
+        let mut key_map = Self::preset(file.preset.unwrap_or(Preset::Arrows));
+        key_map.bindings.extend(file.bindings);
+        Ok(key_map)
+    }
+
+    fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        Self::from_json(&contents)
+    }
+
+    /// The action of a key. A key bound twice triggers the action listed first.
+    pub fn action(&self, key: KeyCode) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    /// Keys bound to more than one action
+    pub fn conflicts(&self) -> Vec<(KeyCode, Vec<Action>)> {
+        let mut actions: BTreeMap<KeyCode, Vec<Action>> = BTreeMap::new();
+        for (action, keys) in self.bindings.iter() {
+            for key in keys.iter() {
+                let bound = actions.entry(*key).or_default();
+                if !bound.contains(action) {
+                    bound.push(*action);
+                }
+            }
+        }
+
+        actions
+            .into_iter()
+            .filter(|(_, actions)| actions.len() > 1)
+            .collect()
+    }
+
+    fn report_conflicts(&self) {
+        for (key, actions) in self.conflicts() {
+            println!(
+                "Key {:?} is bound to {:?}, only {:?} will be used",
+                key, actions, actions[0]
+            );
+        }
+    }
+}
+
+/// Layout of the key map file, everything is optional
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyMapFile {
+    #[serde(default)]
+    preset: Option<Preset>,
+    #[serde(default)]
+    bindings: BTreeMap<Action, Vec<KeyCode>>,
+}
+
+/// The key map file and when it was last read
+pub struct KeyMapWatcher {
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    since_check: f32,
+}
+
+impl KeyMapWatcher {
+    fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path: path,
+            modified: None,
+            since_check: 0.0,
+        }
+    }
+
+    /// The key map when the file changed since it was last read. A deleted
+    /// file goes back to the arrow keys.
+    fn changed(&mut self) -> Option<Result<KeyMap, String>> {
+        let path = self.path.as_ref()?;
+        let modified = fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified == self.modified {
+            return None;
+        }
+
+        self.modified = modified;
+        match modified {
+            Some(_) => Some(KeyMap::load(path)),
+            None => Some(Ok(KeyMap::default())),
+        }
+    }
+}
+
+fn reload(time: Res<Time>, mut watcher: ResMut<KeyMapWatcher>, mut key_map: ResMut<KeyMap>) {
+    watcher.since_check += time.delta_seconds();
+    if watcher.since_check < RELOAD_INTERVAL {
+        return;
+    }
+    watcher.since_check = 0.0;
+
+    match watcher.changed() {
+        Some(Ok(reloaded)) => {
+            println!("Key map reloaded");
+            reloaded.report_conflicts();
+            *key_map = reloaded;
+        }
+        Some(Err(error)) => println!("Could not reload key map, keeping the old one: {}", error),
+        None => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::Duration;
+
+    use crate::storage::test_support::TestDir;
+
+    #[test]
+    fn presets_have_no_conflicts() {
+        for preset in [Preset::Arrows, Preset::Wasd, Preset::Hjkl].iter() {
+            assert_eq!(KeyMap::preset(*preset).conflicts(), vec![]);
+        }
+
+        let hjkl = KeyMap::preset(Preset::Hjkl);
+        assert_eq!(hjkl.action(KeyCode::K), Some(Action::MoveUp));
+        assert_eq!(hjkl.action(KeyCode::Up), Some(Action::MoveUp));
+        assert_eq!(hjkl.action(KeyCode::C), Some(Action::KeepGoing));
+    }
+
+    #[test]
+    fn file_bindings_replace_the_preset_and_conflicts_are_found() {
+        let key_map =
+            KeyMap::from_json(r#"{"preset": "wasd", "bindings": {"undo": ["Z", "W"]}}"#).unwrap();
+
+        assert_eq!(key_map.action(KeyCode::Z), Some(Action::Undo));
+        assert_eq!(key_map.action(KeyCode::U), None);
+        assert_eq!(
+            key_map.conflicts(),
+            vec![(KeyCode::W, vec![Action::MoveUp, Action::Undo])]
+        );
+        assert_eq!(key_map.action(KeyCode::W), Some(Action::MoveUp));
+
+        assert!(KeyMap::from_json(r#"{"bindings": {"fly": ["F"]}}"#).is_err());
+        assert!(KeyMap::from_json(r#"{"bindings": {"undo": ["NotAKey"]}}"#).is_err());
+    }
+
+    #[test]
+    fn changed_file_is_read_again() {
+        let dir = TestDir::new("key-map", "changed");
+        let path = dir.file("keys.json");
+        let write = |contents: &str, age: u64| {
+            fs::write(&path, contents).unwrap();
+            let modified = SystemTime::now() - Duration::from_secs(age);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified)
+                .unwrap();
+        };
+        write(r#"{"preset": "wasd"}"#, 10);
+        let mut watcher = KeyMapWatcher::new(Some(path.clone()));
+
+        assert_eq!(watcher.changed(), Some(Ok(KeyMap::preset(Preset::Wasd))));
+        assert_eq!(watcher.changed(), None);
+
+        write(r#"{"preset": "hjkl"}"#, 0);
+        assert_eq!(watcher.changed(), Some(Ok(KeyMap::preset(Preset::Hjkl))));
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.changed(), Some(Ok(KeyMap::default())));
+    }
+}
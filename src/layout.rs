@@ -0,0 +1,181 @@
+use crate::logic::BoardConfig;
+
+/// Size of the window the game opens in
+pub const WIDTH: f32 = 480.0;
+pub const HEIGHT: f32 = 640.0;
+/// Smallest window the header and a readable board still fit in
+pub const MIN_WIDTH: f32 = 320.0;
+pub const MIN_HEIGHT: f32 = 320.0;
+const GAP: f32 = 10.0;
+/// Room around the header and the field, in header cells
+const HEADER_INDENT: f32 = 0.24;
+/// Room the header takes above the field in portrait, in header cells
+const HEADER_HEIGHT: f32 = 1.5625;
+/// Room between the score boxes
+pub const BOX_MARGIN: f32 = 5.0;
+
+/// Size and placement of the header and the field, scaled so the configured
+/// board fits the window. A window wider than tall gets the header beside the
+/// field instead of above it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub cell_size: f32,
+    pub columns: i32,
+    pub rows: i32,
+    pub landscape: bool,
+    /// Size of the logo, the rest of the header scales with it
+    pub header_cell_size: f32,
+    pub header_indent: f32,
+    pub field_width: f32,
+    pub field_height: f32,
+    pub left_indent: f32,
+    pub top_indent: f32,
+}
+
+impl Layout {
+    pub fn new(config: &BoardConfig, width: f32, height: f32) -> Self {
+        let landscape = width > height;
+        // The header keeps the proportions it has in the 480x640 window whatever the board size
+        let (long_side, short_side) = if landscape {
+            (width, height)
+        } else {
+            (height, width)
+        };
+        let header_cell_size = f32::min(short_side / 5.0, long_side * 0.15);
+        let header_indent = header_cell_size * HEADER_INDENT;
+
+        // Largest area the field may take up, the 4x4 board fills its width exactly in portrait
+        let (area_left, area_top) = if landscape {
+            (
+                Self::header_width(header_cell_size, header_indent),
+                header_indent,
+            )
+        } else {
+            (header_indent, header_cell_size * HEADER_HEIGHT)
+        };
+        let area_width = width - area_left - header_indent;
+        let area_height = height - area_top - header_indent * if landscape { 1.0 } else { 2.0 };
+
+        let columns = config.width as f32;
+        let rows = config.height as f32;
+        let cell_size = f32::max(
+            f32::min(
+                (area_width - GAP * (columns + 1.0)) / columns,
+                (area_height - GAP * (rows + 1.0)) / rows,
+            ),
+            1.0,
+        );
+        let field_width = GAP + (cell_size + GAP) * columns;
+        let field_height = GAP + (cell_size + GAP) * rows;
+        let top_indent = if landscape {
+            area_top + (area_height - field_height) / 2.0
+        } else {
+            area_top
+        };
+
+        Self {
+            cell_size: cell_size,
+            columns: config.width,
+            rows: config.height,
+            landscape: landscape,
+            header_cell_size: header_cell_size,
+            header_indent: header_indent,
+            field_width: field_width,
+            field_height: field_height,
+            left_indent: area_left + (area_width - field_width) / 2.0,
+            top_indent: top_indent,
+        }
+    }
+
+    /// Width of the header column in landscape, as wide as the score boxes
+    pub fn header_width(header_cell_size: f32, header_indent: f32) -> f32 {
+        header_cell_size * 1.5 + 2.0 * (header_indent + BOX_MARGIN)
+    }
+
+    /// Position of a cell relative to the field
+    pub fn cell_offset(&self, number: i32) -> f32 {
+        GAP + (self.cell_size + GAP) * number as f32
+    }
+
+    pub fn column_x(&self, number: i32) -> f32 {
+        self.left_indent + self.cell_offset(number)
+    }
+
+    pub fn row_y(&self, number: i32) -> f32 {
+        self.top_indent + self.cell_offset(number)
+    }
+
+    /// Where a point placed for the `previous` layout goes in this one, the
+    /// corner of a cell stays the corner of the same cell
+    pub fn moved_from(&self, previous: &Layout, point: (f32, f32)) -> (f32, f32) {
+        let scale = (self.cell_size + GAP) / (previous.cell_size + GAP);
+        (
+            self.left_indent + GAP + (point.0 - previous.left_indent - GAP) * scale,
+            self.top_indent + GAP + (point.1 - previous.top_indent - GAP) * scale,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fits(layout: &Layout, width: f32, height: f32) -> bool {
+        let right = layout.column_x(layout.columns - 1) + layout.cell_size;
+        let bottom = layout.row_y(layout.rows - 1) + layout.cell_size;
+        layout.column_x(0) > 0.0 && right < width && bottom < height
+    }
+
+    #[test]
+    fn the_opening_window_keeps_the_classic_layout() {
+        let layout = Layout::new(&BoardConfig::default(), WIDTH, HEIGHT);
+
+        assert!(!layout.landscape);
+        assert_eq!(layout.header_cell_size, 96.0);
+        assert_eq!(layout.row_y(0), 160.0);
+        assert!((layout.column_x(0) - 33.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn wide_windows_put_the_header_beside_the_field() {
+        let config = BoardConfig::new(5, 3).unwrap();
+        for (width, height) in [
+            (900.0, 500.0),
+            (MIN_WIDTH + 1.0, MIN_HEIGHT),
+            (2000.0, 400.0),
+        ]
+        .iter()
+        {
+            let layout = Layout::new(&config, *width, *height);
+
+            assert!(layout.landscape);
+            assert!(fits(&layout, *width, *height));
+            assert!(
+                layout.column_x(0)
+                    > Layout::header_width(layout.header_cell_size, layout.header_indent)
+            );
+        }
+
+        for (width, height) in [(MIN_WIDTH, MIN_HEIGHT), (400.0, 1200.0)].iter() {
+            let layout = Layout::new(&config, *width, *height);
+
+            assert!(!layout.landscape);
+            assert!(fits(&layout, *width, *height));
+        }
+    }
+
+    #[test]
+    fn cells_stay_on_their_cells_after_a_resize() {
+        let config = BoardConfig::new(4, 6).unwrap();
+        let before = Layout::new(&config, WIDTH, HEIGHT);
+        let after = Layout::new(&config, 1000.0, 700.0);
+
+        for row in 0..config.height {
+            for col in 0..config.width {
+                let (x, y) = after.moved_from(&before, (before.column_x(col), before.row_y(row)));
+                assert!((x - after.column_x(col)).abs() < 1e-3);
+                assert!((y - after.row_y(row)).abs() < 1e-3);
+            }
+        }
+    }
+}
@@ -1,185 +1,146 @@
+use std::collections::VecDeque;
+
 use bevy::prelude::*;
-use rand::Rng;
 
-pub mod number;
-pub mod position_map;
+pub use rules::{history, number, position_map, rng, BoardConfig, WinConfig};
 
-use number::Number;
-use position_map::{Direction, Id, Position, PositionMap};
+use history::UndoConfig;
+use position_map::{Direction, Id};
+use rng::RngConfig;
+use rules::{Game, GameConfig, MoveOutcome, Replay, Turn};
 
 use crate::{
     events::{
-        AnimationCompleted, BlockAdded, BlocksDeleted, BlocksMoved, GameOver, GameRestarted,
-        MoveRequested, RestartRequested,
+        AnimationCompleted, AnimationSkipRequested, BlockAdded, BlockOrigin, BlocksDeleted,
+        BlocksMoved, BoardRestored, GameContinued, GameOver, GameRestarted, GameWon,
+        KeepGoingRequested, MoveRequested, RestartRequested, ScoreChanged, ScoreGained,
+        UndoRequested,
     },
     stages::CustomStage,
 };
 
-pub enum GenerateResult {
-    GameOver,
-    BlockAdded(Id, Number, Position),
+/// How many moves are kept while a move is animating, and whether a new
+/// one cuts the running animation short.
+#[derive(Debug, Clone, Copy)]
+pub struct InputBufferConfig {
+    pub depth: usize,
+    pub fast_forward: bool,
+}
+
+impl Default for InputBufferConfig {
+    fn default() -> Self {
+        Self {
+            depth: 2,
+            fast_forward: false,
+        }
+    }
 }
 
-pub enum MoveBlockResult {
-    None,
-    GameOver,
-    Success(BlocksMoved),
+/// Something the player asked for while a move was animating
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Queued {
+    Move(Direction),
+    Undo,
 }
 
+/// Every block with its exponent and cell, to tell whether the board
+/// changed while a search was running on it
+pub type BoardKey = Vec<(Id, u32, i32, i32)>;
+
+/// The game as seen by the rest of the app. The rules apply a move at
+/// once, its merges and the new block are only announced when the
+/// animation of the move has completed.
 #[derive(Debug)]
 pub struct LogicState {
-    pub position_map: PositionMap,
-    pub current_id: Id,
-    pub is_game_over: bool,
-    pub ready_for_next_move: bool,
-    pub merges: Vec<(i32, i32, Position)>,
+    pub game: Game,
+    /// Move waiting for its animation to complete
+    pub pending: Option<Turn>,
+    /// Moves and undos requested during the animation, applied in order once it completes
+    pub queued: VecDeque<Queued>,
+    /// Waiting for the player to keep going or start a new game after winning
+    pub is_won: bool,
+    /// The board was replaced this frame, its blocks are only spawned in the next one
+    pub board_replaced: bool,
+    /// Everything done since the game started, `None` for a resumed game
+    /// as it no longer follows from its seed
+    pub recording: Option<Replay>,
 }
 
 impl LogicState {
-    pub fn new() -> Self {
-        LogicState {
-            position_map: PositionMap::new(),
-            current_id: 0,
-            is_game_over: false,
-            ready_for_next_move: true,
-            merges: vec![],
+    pub fn new(game: Game) -> Self {
+        let recording = Replay::start(&game);
+
+        Self {
+            game: game,
+            pending: None,
+            queued: VecDeque::new(),
+            is_won: false,
+            board_replaced: false,
+            recording: Some(recording),
         }
     }
 
-    pub fn restart(&mut self) {
-        self.position_map = PositionMap::new();
-        self.current_id = 0;
-        self.is_game_over = false;
-        self.ready_for_next_move = true;
+    pub fn ready_for_next_move(&self) -> bool {
+        self.pending.is_none()
     }
 
-    pub fn add_block(&mut self, number: Number, position: Position) -> i32 {
-        let id = self.current_id;
-        self.current_id += 1;
-
-        self.position_map.add_block(id, number);
-        self.position_map.set(position.x, position.y, Some(id));
-        id
+    pub fn board_key(&self) -> BoardKey {
+        self.game
+            .board()
+            .blocks()
+            .into_iter()
+            .map(|(id, number, position)| (id, number.exponent(), position.x, position.y))
+            .collect()
     }
 
-    pub fn generate_block(&mut self) -> GenerateResult {
-        let random: f32 = rand::thread_rng().gen_range(0.0..1.0);
-        let number = if random > 0.9 {
-            Number::ONE
-        } else {
-            Number::ZERO
-        };
-
-        if let Some(position) = self.position_map.get_random_free_position() {
-            let id = self.add_block(number, position);
-            GenerateResult::BlockAdded(id, number, position)
-        } else {
-            GenerateResult::GameOver
-        }
+    /// Ready for a move and showing the current board, a move on a board replaced
+    /// this frame would animate the blocks about to be despawned
+    fn settled(&self) -> bool {
+        self.ready_for_next_move() && !self.board_replaced
     }
 
-    pub fn move_blocks_to(&mut self, direction: Direction) -> MoveBlockResult {
-        if !self.ready_for_next_move {
-            println!("Ignoring requested move");
-            return MoveBlockResult::None;
-        }
-
-        if !self.position_map.has_available_moves() {
-            if !self.is_game_over {
-                self.is_game_over = true;
-                return MoveBlockResult::GameOver;
-            }
-            return MoveBlockResult::None;
-        }
-
-        // Move blocks
-        let mut moves: Vec<(i32, Position)> = vec![];
-        let mut merges: Vec<(i32, i32, Position)> = vec![];
-
-        let new_map = self.calculate_new_map(direction, &mut moves, &mut merges);
-        new_map.print_map();
-
-        if !new_map.same_positions(&self.position_map) {
-            // Wait for dependent plugins to be ready for next move, i.e animation
-            self.ready_for_next_move = false;
-
-            // This may need to go after animations completed
-            self.position_map = new_map;
-            let mut merged_moves = moves.clone();
-            for (id1, id2, pos) in merges.iter() {
-                merged_moves.push((*id1, pos.clone()));
-                merged_moves.push((*id2, *pos));
+    /// Applies a move, returning false when nothing could move
+    fn apply(&mut self, direction: Direction, blocks_moved: &mut EventWriter<BlocksMoved>) -> bool {
+        match self.game.apply(direction) {
+            MoveOutcome::Moved(turn) => {
+                if let Some(recording) = self.recording.as_mut() {
+                    recording.record_move(direction, &turn);
+                }
+                blocks_moved.send(BlocksMoved {
+                    moves: turn.moves.clone(),
+                });
+                self.pending = Some(turn);
+                true
             }
-
-            self.merges = merges.clone();
-
-            MoveBlockResult::Success(BlocksMoved {
-                moves: merged_moves,
-            })
-        } else {
-            MoveBlockResult::None
+            MoveOutcome::Blocked => false,
         }
     }
 
-    pub fn calculate_new_map(
+    /// Keeps a request for once the animation has completed, unless the buffer is full
+    fn enqueue(
         &mut self,
-        direction: Direction,
-        moves: &mut Vec<(i32, Position)>,
-        merges: &mut Vec<(i32, i32, Position)>,
-    ) -> PositionMap {
-        let mut new_map = self.position_map.new_with_existing_blocks();
-        let start_index = match direction {
-            Direction::LEFT | Direction::TOP => 0,
-            _ => 3,
-        };
-
-        #[allow(unused_assignments)]
-        let mut column_row = start_index;
-
-        let max: i32 = 3;
-        for line in 0..=max {
-            let mut cur_pos = self
-                .position_map
-                .get_not_empty_position_from(direction, line);
-            column_row = start_index;
-
-            loop {
-                if let Some(cur_pos) = cur_pos {
-                    let new_pos = new_position(line, &mut column_row, direction);
-                    let current_id = self.position_map.get(cur_pos.x, cur_pos.y);
-                    self.position_map.set(cur_pos.x, cur_pos.y, None);
-
-                    let next_pos = self
-                        .position_map
-                        .get_not_empty_position_from(direction, line);
-
-                    let next_id = next_pos.map_or(None, |p| self.position_map.get(p.x, p.y));
-
-                    let current_number =
-                        current_id.map_or(None, |id| self.position_map.get_number_with_id(id));
-                    let next_number =
-                        next_id.map_or(None, |id| self.position_map.get_number_with_id(id));
-
-                    if next_id.is_some() && current_id.is_some() && current_number == next_number {
-                        // merge these blocks
-                        self.position_map.set(cur_pos.x, cur_pos.y, None);
-                        new_map.set(cur_pos.x, cur_pos.y, current_id);
-                        merges.push((current_id.unwrap(), next_id.unwrap(), new_pos));
-                    } else {
-                        new_map.set(new_pos.x, new_pos.y, current_id);
-                        moves.push((current_id.unwrap(), new_pos))
-                    }
-                } else {
-                    break;
-                }
-
-                cur_pos = self
-                    .position_map
-                    .get_not_empty_position_from(direction, line);
+        queued: Queued,
+        buffer: &InputBufferConfig,
+        skip: &mut EventWriter<AnimationSkipRequested>,
+    ) {
+        if self.queued.len() < buffer.depth {
+            self.queued.push_back(queued);
+            if buffer.fast_forward {
+                skip.send(AnimationSkipRequested);
             }
+        } else {
+            println!(
+                "Ignoring requested {:?}, {} already queued",
+                queued, buffer.depth
+            );
         }
+    }
 
-        new_map
+    /// Dismisses the win so play can carry on past the target
+    pub fn keep_going(&mut self) -> bool {
+        let was_won = self.is_won;
+        self.is_won = false;
+        was_won
     }
 }
 
@@ -187,40 +148,93 @@ pub struct LogicPlugin;
 
 impl Plugin for LogicPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.insert_resource(LogicState::new())
-            .add_startup_system(generate_starting_block.system())
+        let board = *app
+            .world_mut()
+            .get_resource_or_insert_with(BoardConfig::default);
+
+        let undo = *app
+            .world_mut()
+            .get_resource_or_insert_with(UndoConfig::default);
+
+        let rng = *app
+            .world_mut()
+            .get_resource_or_insert_with(RngConfig::default);
+
+        let win = *app
+            .world_mut()
+            .get_resource_or_insert_with(WinConfig::default);
+
+        app.world_mut()
+            .get_resource_or_insert_with(InputBufferConfig::default);
+
+        let game = Game::new(GameConfig {
+            board: board,
+            undo: undo,
+            rng: rng,
+            win: win,
+        });
+
+        app.insert_resource(LogicState::new(game))
+            .add_startup_system(announce_starting_blocks.system())
+            .add_system_to_stage(CustomStage::Before, board_replaced_reset.system())
             .add_system_to_stage(CustomStage::After, move_requested_listener.system())
             .add_system(restart_request_listener.system())
-            .add_system(game_restarted_listener.system())
+            .add_system(undo_requested_listener.system())
+            .add_system(keep_going_listener.system())
             .add_system(animation_completed.system());
     }
 }
 
-fn generate_starting_block(mut state: ResMut<LogicState>, mut events: EventWriter<BlockAdded>) {
-    if let GenerateResult::BlockAdded(id, number, position) = state.generate_block() {
-        println!("Block added!");
-        events.send(BlockAdded {
-            id: id,
-            number: number,
-            position: position,
-        });
-    } else {
-        panic!("Unexpected result during first block generation")
+fn announce_starting_blocks(state: Res<LogicState>, mut events: EventWriter<BlockAdded>) {
+    announce_blocks(&state, &mut events);
+}
+
+fn board_replaced_reset(mut state: ResMut<LogicState>) {
+    if state.board_replaced {
+        state.board_replaced = false;
     }
 }
 
 fn move_requested_listener(
     mut state: ResMut<LogicState>,
+    buffer: Res<InputBufferConfig>,
     mut move_events: EventReader<MoveRequested>,
-    mut game_over: EventWriter<GameOver>,
     mut blocks_moved: EventWriter<BlocksMoved>,
+    mut skip: EventWriter<AnimationSkipRequested>,
+    mut restored: EventWriter<BoardRestored>,
+    mut block_added: EventWriter<BlockAdded>,
+    mut score_changed: EventWriter<ScoreChanged>,
 ) {
+    // Checked first so that the state is only touched when there is a queued move
+    if !state.queued.is_empty() && state.settled() && !state.is_won {
+        while let Some(queued) = state.queued.pop_front() {
+            let done = match queued {
+                Queued::Move(direction) => state.apply(direction, &mut blocks_moved),
+                // The restored blocks are only spawned in the next frame, the
+                // requests queued after the undo wait for them
+                Queued::Undo => undo(
+                    &mut state,
+                    &mut restored,
+                    &mut block_added,
+                    &mut score_changed,
+                ),
+            };
+            if done {
+                break;
+            }
+        }
+    }
+
     for move_event in move_events.iter() {
-        println!("State: {:?}", *state);
-        match state.move_blocks_to(move_event.direction) {
-            MoveBlockResult::None => (),
-            MoveBlockResult::GameOver => game_over.send(GameOver),
-            MoveBlockResult::Success(event) => blocks_moved.send(event),
+        if state.is_won {
+            println!("Ignoring requested move");
+            continue;
+        }
+
+        if state.settled() && state.queued.is_empty() {
+            state.apply(move_event.direction, &mut blocks_moved);
+        } else {
+            state.enqueue(Queued::Move(move_event.direction), &buffer, &mut skip);
         }
     }
 }
@@ -229,118 +243,391 @@ fn restart_request_listener(
     mut state: ResMut<LogicState>,
     mut events: EventReader<RestartRequested>,
     mut restarted: EventWriter<GameRestarted>,
+    mut block_added: EventWriter<BlockAdded>,
+    mut score_changed: EventWriter<ScoreChanged>,
 ) {
     for _ in events.iter() {
-        if state.is_game_over {
-            state.restart();
+        if state.ready_for_next_move() {
+            state.game.restart();
+            state.is_won = false;
+            state.queued.clear();
+            state.recording = Some(Replay::start(&state.game));
             restarted.send(GameRestarted);
+            announce_blocks(&state, &mut block_added);
+            score_changed.send(ScoreChanged {
+                score: state.game.score(),
+            });
         }
     }
 }
 
-fn game_restarted_listener(
+fn keep_going_listener(
     mut state: ResMut<LogicState>,
-    mut events: EventReader<GameRestarted>,
-    mut block_added: EventWriter<BlockAdded>,
+    mut events: EventReader<KeepGoingRequested>,
+    mut continued: EventWriter<GameContinued>,
+    mut game_over: EventWriter<GameOver>,
 ) {
     for _ in events.iter() {
-        if !state.position_map.has_any_blocks() {
-            if let GenerateResult::BlockAdded(id, number, position) = state.generate_block() {
-                println!("Block added!");
-                block_added.send(BlockAdded {
-                    id: id,
-                    number: number,
-                    position: position,
-                });
-            } else {
-                panic!("Unexpected result during first block generation")
+        if state.keep_going() {
+            continued.send(GameContinued);
+            // The winning move may also have been the last one
+            if state.game.is_game_over() {
+                game_over.send(GameOver);
             }
         }
     }
 }
 
+/// Undoes at once between moves, otherwise after the moves queued before it
+fn undo_requested_listener(
+    mut state: ResMut<LogicState>,
+    buffer: Res<InputBufferConfig>,
+    mut events: EventReader<UndoRequested>,
+    mut skip: EventWriter<AnimationSkipRequested>,
+    mut restored: EventWriter<BoardRestored>,
+    mut block_added: EventWriter<BlockAdded>,
+    mut score_changed: EventWriter<ScoreChanged>,
+) {
+    for _ in events.iter() {
+        if state.ready_for_next_move() && state.queued.is_empty() {
+            undo(
+                &mut state,
+                &mut restored,
+                &mut block_added,
+                &mut score_changed,
+            );
+        } else {
+            state.enqueue(Queued::Undo, &buffer, &mut skip);
+        }
+    }
+}
+
+/// Takes back the last move, returning false when there is nothing to undo
+fn undo(
+    state: &mut LogicState,
+    restored: &mut EventWriter<BoardRestored>,
+    block_added: &mut EventWriter<BlockAdded>,
+    score_changed: &mut EventWriter<ScoreChanged>,
+) -> bool {
+    if !state.game.undo() {
+        return false;
+    }
+
+    state.is_won = false;
+    if let Some(recording) = state.recording.as_mut() {
+        recording.record_undo();
+    }
+    announce_board(state, restored, block_added, score_changed);
+    true
+}
+
+/// Tells the other plugins that the whole board was replaced, e.g. by undo
+pub fn announce_board(
+    state: &mut LogicState,
+    restored: &mut EventWriter<BoardRestored>,
+    block_added: &mut EventWriter<BlockAdded>,
+    score_changed: &mut EventWriter<ScoreChanged>,
+) {
+    state.board_replaced = true;
+    restored.send(BoardRestored);
+    announce_blocks(state, block_added);
+    score_changed.send(ScoreChanged {
+        score: state.game.score(),
+    });
+}
+
+fn announce_blocks(state: &LogicState, block_added: &mut EventWriter<BlockAdded>) {
+    for (id, number, position) in state.game.board().blocks() {
+        block_added.send(BlockAdded {
+            id: id,
+            number: number,
+            position: position,
+            origin: BlockOrigin::Placed,
+        });
+    }
+}
+
 fn animation_completed(
     mut state: ResMut<LogicState>,
     mut events: EventReader<AnimationCompleted>,
     mut block_added: EventWriter<BlockAdded>,
     mut deleted_blocks: EventWriter<BlocksDeleted>,
+    mut score_changed: EventWriter<ScoreChanged>,
+    mut score_gained: EventWriter<ScoreGained>,
+    mut game_won: EventWriter<GameWon>,
+    mut game_over: EventWriter<GameOver>,
 ) {
     for _ in events.iter() {
-        if !state.ready_for_next_move {
-            // Deal with merges
-            let mut deleted: Vec<i32> = vec![];
-            let mut added: Vec<(i32, Number, Position)> = vec![];
-
-            let length = state.merges.len();
-            for i in 0..length {
-                let (id1, id2, position) = state.merges[i];
-                println!("{:?}", *state);
-                let next_number = state
-                    .position_map
-                    .get_number_with_id(id1)
-                    .expect(&format!(
-                        "Attempted to retrieve id {} but was not found",
-                        id1
-                    ))
-                    .next();
-                state.position_map.delete_block(id1);
-                state.position_map.delete_block(id2);
-
-                deleted.push(id1);
-                deleted.push(id2);
-
-                let id = state.add_block(next_number, position);
-                added.push((id, next_number, position));
-            }
-
-            if deleted.len() > 0 {
-                deleted_blocks.send(BlocksDeleted { deleted: deleted })
-            }
+        // Taking the turn only when there is one keeps the state unchanged between moves
+        if state.ready_for_next_move() {
+            continue;
+        }
+        let turn = state.pending.take().unwrap();
+
+        if turn.merges.len() > 0 {
+            deleted_blocks.send(BlocksDeleted {
+                deleted: turn
+                    .merges
+                    .iter()
+                    .flat_map(|merge| [merge.merged.0, merge.merged.1])
+                    .collect(),
+            })
+        }
 
-            for (id, number, position) in added.iter() {
-                block_added.send(BlockAdded {
-                    id: *id,
-                    number: *number,
-                    position: *position,
-                });
-            }
+        for merge in turn.merges.iter() {
+            block_added.send(BlockAdded {
+                id: merge.id,
+                number: merge.number,
+                position: merge.position,
+                origin: BlockOrigin::Merged,
+            });
+        }
 
-            state.merges = vec![];
+        if turn.points > 0 {
+            score_changed.send(ScoreChanged {
+                score: state.game.score(),
+            });
+            score_gained.send(ScoreGained {
+                points: turn.points,
+            });
+        }
 
-            if let GenerateResult::BlockAdded(id, number, position) = state.generate_block() {
-                println!("Block added!");
-                block_added.send(BlockAdded {
-                    id: id,
-                    number: number,
-                    position: position,
-                });
-            } else {
-                panic!("Unexpected result during first block generation")
-            }
+        if let Some((id, number, position)) = turn.spawned {
+            block_added.send(BlockAdded {
+                id: id,
+                number: number,
+                position: position,
+                origin: BlockOrigin::Spawned,
+            });
+        }
 
-            state.ready_for_next_move = true;
+        if turn.won {
+            // Moves made before the win was shown shouldn't play past it
+            state.is_won = true;
+            state.queued.clear();
+            game_won.send(GameWon);
+        } else if turn.game_over {
+            game_over.send(GameOver);
         }
     }
 }
 
-fn new_position(line: i32, column_row: &mut i32, direction: Direction) -> Position {
-    let tmp = *column_row;
-    match direction {
-        Direction::LEFT => {
-            *column_row += 1;
-            Position { x: tmp, y: line }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{events::EventRegistrationPlugin, stages::StagePlugin};
+    use bevy::app::Events;
+    use number::Number;
+
+    fn test_app(seed: u64, buffer: InputBufferConfig) -> AppBuilder {
+        let mut app = App::build();
+        app.insert_resource(RngConfig { seed: Some(seed) })
+            .insert_resource(buffer)
+            .add_plugin(EventRegistrationPlugin)
+            .add_plugin(StagePlugin)
+            .add_plugin(LogicPlugin);
+        app.app.update();
+        app
+    }
+
+    fn send<T: Send + Sync + 'static>(app: &mut AppBuilder, event: T) {
+        app.world_mut()
+            .get_resource_mut::<Events<T>>()
+            .unwrap()
+            .send(event);
+    }
+
+    fn blocks(game: &Game) -> Vec<(Number, i32, i32)> {
+        let mut blocks: Vec<(Number, i32, i32)> = game
+            .board()
+            .blocks()
+            .into_iter()
+            .map(|(_, number, position)| (number, position.x, position.y))
+            .collect();
+        blocks.sort_by_key(|(number, x, y)| (*x, *y, number.value()));
+        blocks
+    }
+
+    fn state(app: &AppBuilder) -> &LogicState {
+        app.app.world.get_resource::<LogicState>().unwrap()
+    }
+
+    fn run_game(seed: u64, moves: &[Direction]) -> Vec<(Number, i32, i32)> {
+        let mut app = test_app(seed, InputBufferConfig::default());
+
+        for direction in moves.iter() {
+            send(&mut app, MoveRequested::new(*direction));
+            app.app.update();
+
+            // Stand in for the animation plugin
+            send(&mut app, AnimationCompleted);
+            app.app.update();
+        }
+
+        blocks(&state(&app).game)
+    }
+
+    /// The board after playing the moves straight on the rules
+    fn played(seed: u64, moves: &[Direction]) -> Vec<(Number, i32, i32)> {
+        let mut game = Game::new(GameConfig {
+            rng: RngConfig { seed: Some(seed) },
+            ..GameConfig::default()
+        });
+        for direction in moves.iter() {
+            game.apply(*direction);
+        }
+        blocks(&game)
+    }
+
+    #[test]
+    fn same_seed_and_moves_produce_same_board() {
+        let moves = [
+            Direction::LEFT,
+            Direction::TOP,
+            Direction::RIGHT,
+            Direction::BOTTOM,
+            Direction::LEFT,
+            Direction::LEFT,
+            Direction::TOP,
+            Direction::RIGHT,
+        ];
+
+        let first = run_game(2048, &moves);
+        let second = run_game(2048, &moves);
+
+        assert!(first.len() > 1);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn different_seeds_produce_different_games() {
+        let moves = [Direction::LEFT, Direction::TOP, Direction::RIGHT];
+
+        let games: Vec<_> = (0..8).map(|seed| run_game(seed, &moves)).collect();
+
+        assert!(games.iter().any(|game| *game != games[0]));
+    }
+
+    fn play_quickly(depth: usize, moves: &[Direction]) -> Vec<(Number, i32, i32)> {
+        let mut app = test_app(
+            5,
+            InputBufferConfig {
+                depth: depth,
+                fast_forward: false,
+            },
+        );
+
+        // Every move arrives while the first one is animating
+        for direction in moves.iter() {
+            send(&mut app, MoveRequested::new(*direction));
         }
-        Direction::RIGHT => {
-            *column_row -= 1;
-            Position { x: tmp, y: line }
+        app.app.update();
+        for _ in moves.iter() {
+            send(&mut app, AnimationCompleted);
+            app.app.update();
         }
-        Direction::TOP => {
-            *column_row += 1;
-            Position { x: line, y: tmp }
+
+        assert!(state(&app).queued.is_empty());
+        blocks(&state(&app).game)
+    }
+
+    #[test]
+    fn moves_sent_during_an_animation_are_applied_in_order() {
+        let moves = [Direction::LEFT, Direction::TOP, Direction::RIGHT];
+
+        assert_eq!(play_quickly(2, &moves), played(5, &moves));
+    }
+
+    #[test]
+    fn undo_sent_during_an_animation_keeps_its_place_among_the_moves() {
+        let mut app = test_app(5, InputBufferConfig::default());
+
+        // Undo and a move while the first move animates
+        send(&mut app, MoveRequested::new(Direction::LEFT));
+        app.app.update();
+        send(&mut app, UndoRequested);
+        send(&mut app, MoveRequested::new(Direction::TOP));
+        app.app.update();
+        for _ in 0..3 {
+            send(&mut app, AnimationCompleted);
+            app.app.update();
         }
-        Direction::BOTTOM => {
-            *column_row -= 1;
-            Position { x: line, y: tmp }
+
+        let mut game = Game::new(GameConfig {
+            rng: RngConfig { seed: Some(5) },
+            ..GameConfig::default()
+        });
+        game.apply(Direction::LEFT);
+        game.undo();
+        game.apply(Direction::TOP);
+        assert!(state(&app).queued.is_empty());
+        assert_eq!(blocks(&state(&app).game), blocks(&game));
+
+        // Undo arriving in the frame the animation completes, with a move still queued
+        let mut app = test_app(5, InputBufferConfig::default());
+        send(&mut app, MoveRequested::new(Direction::LEFT));
+        app.app.update();
+        send(&mut app, MoveRequested::new(Direction::TOP));
+        app.app.update();
+        send(&mut app, AnimationCompleted);
+        send(&mut app, UndoRequested);
+        app.app.update();
+        for _ in 0..2 {
+            send(&mut app, AnimationCompleted);
+            app.app.update();
         }
+
+        let mut game = Game::new(GameConfig {
+            rng: RngConfig { seed: Some(5) },
+            ..GameConfig::default()
+        });
+        game.apply(Direction::LEFT);
+        game.apply(Direction::TOP);
+        game.undo();
+        assert!(state(&app).queued.is_empty());
+        assert_eq!(blocks(&state(&app).game), blocks(&game));
+    }
+
+    #[test]
+    fn move_sent_with_an_undo_waits_for_the_restored_board() {
+        let mut app = test_app(5, InputBufferConfig::default());
+        send(&mut app, MoveRequested::new(Direction::LEFT));
+        app.app.update();
+        send(&mut app, AnimationCompleted);
+        app.app.update();
+
+        send(&mut app, UndoRequested);
+        send(&mut app, MoveRequested::new(Direction::TOP));
+        app.app.update();
+
+        assert!(state(&app).pending.is_none());
+        assert_eq!(
+            state(&app).queued,
+            VecDeque::from(vec![Queued::Move(Direction::TOP)])
+        );
+        assert_eq!(blocks(&state(&app).game), played(5, &[]));
+
+        app.app.update();
+        assert!(state(&app).pending.is_some());
+        send(&mut app, AnimationCompleted);
+        app.app.update();
+
+        let mut game = Game::new(GameConfig {
+            rng: RngConfig { seed: Some(5) },
+            ..GameConfig::default()
+        });
+        game.apply(Direction::LEFT);
+        game.undo();
+        game.apply(Direction::TOP);
+        assert!(state(&app).queued.is_empty());
+        assert_eq!(blocks(&state(&app).game), blocks(&game));
+    }
+
+    #[test]
+    fn moves_past_the_buffer_depth_are_dropped() {
+        let moves = [Direction::LEFT, Direction::TOP, Direction::RIGHT];
+
+        assert_eq!(play_quickly(1, &moves), played(5, &moves[..2]));
+        assert_eq!(play_quickly(0, &moves), played(5, &moves[..1]));
     }
 }
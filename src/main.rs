@@ -1,51 +1,100 @@
 use bevy::prelude::*;
-use events::{EventRegistrationPlugin, ScoreChanged};
+use bevy::window::WindowResizeConstraints;
+use events::EventRegistrationPlugin;
 
 mod animation;
+mod app_state;
+mod autoplay;
+mod best_score;
 mod events;
+mod gesture;
+mod hint;
 mod input;
+mod key_map;
+mod layout;
 mod logic;
+mod options;
+mod replay;
+mod save;
 mod stages;
+mod storage;
 mod ui_plugin;
 
-struct State {
-    pub timer: Timer,
-    pub score: i32,
-}
+fn main() {
+    let mut options = options::Options::from_args();
 
-impl Default for State {
-    fn default() -> Self {
-        Self {
-            timer: Timer::from_seconds(1.0, true),
-            score: 0,
-        }
+    // A replay brings its own board and seed
+    let replay = options
+        .replay
+        .as_deref()
+        .map(|path| match replay::load(path) {
+            Ok(replay) => replay,
+            Err(error) => {
+                eprintln!("Could not load replay {}: {}", path.display(), error);
+                std::process::exit(2);
+            }
+        });
+    if let Some(replay) = &replay {
+        let config = replay.config();
+        options.board = config.board;
+        options.undo = config.undo;
+        options.rng = config.rng;
     }
-}
 
-fn main() {
-    App::build()
+    let mut app = App::build();
+    app.insert_resource(options.board)
+        .insert_resource(options.undo)
+        .insert_resource(options.rng)
+        .insert_resource(options.win)
+        .insert_resource(options.search)
+        .insert_resource(options.input_buffer)
+        .insert_resource(options.animation)
+        .insert_resource(options.gesture)
+        .insert_resource(options.theme)
         .insert_resource(WindowDescriptor {
             title: "Hello Bevy!".to_string(),
-            width: ui_plugin::WIDTH,
-            height: ui_plugin::HEIGHT,
+            width: layout::WIDTH,
+            height: layout::HEIGHT,
+            resize_constraints: WindowResizeConstraints {
+                min_width: layout::MIN_WIDTH,
+                min_height: layout::MIN_HEIGHT,
+                ..Default::default()
+            },
+            resizable: true,
             ..Default::default()
         })
         .add_plugins(DefaultPlugins)
-        .insert_resource(State::default())
         .add_plugin(EventRegistrationPlugin)
         .add_plugin(stages::StagePlugin)
         .add_plugin(logic::LogicPlugin)
-        .add_plugin(input::InputPlugin)
+        .add_plugin(app_state::AppStatePlugin {
+            // A replay has no menu, it starts playing at once
+            start: if replay.is_some() {
+                app_state::AppState::Playing
+            } else {
+                app_state::AppState::MainMenu
+            },
+        });
+
+    match replay {
+        // Watching a replay neither saves it over the player's game nor counts towards the best score
+        Some(replay) => {
+            app.insert_resource(replay::Playback::new(replay, options.playback))
+                .add_plugin(replay::PlaybackPlugin);
+        }
+        None => {
+            app.add_plugin(best_score::BestScorePlugin)
+                .add_plugin(save::SavePlugin)
+                .add_plugin(key_map::KeyMapPlugin)
+                .add_plugin(input::InputPlugin)
+                .add_plugin(gesture::GesturePlugin)
+                .add_plugin(autoplay::AutoplayPlugin)
+                .add_plugin(replay::RecordingPlugin);
+        }
+    }
+
+    app.add_plugin(hint::HintPlugin)
         .add_plugin(ui_plugin::UIPlugin)
         .add_plugin(animation::AnimationPlugin)
-        .add_system(score_changer.system())
         .run();
 }
-
-fn score_changer(time: Res<Time>, mut state: ResMut<State>, mut events: EventWriter<ScoreChanged>) {
-    if state.timer.tick(time.delta()).just_finished() {
-        state.score += 1;
-
-        events.send(ScoreChanged { score: state.score });
-    }
-}
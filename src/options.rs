@@ -0,0 +1,217 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use rules::ai::SearchConfig;
+
+use crate::animation::{AnimationConfig, Easing};
+use crate::gesture::GestureConfig;
+use crate::logic::{
+    history::UndoConfig, number::Number, rng::RngConfig, BoardConfig, InputBufferConfig, WinConfig,
+};
+use crate::replay::PlaybackConfig;
+use crate::ui_plugin::theme::Theme;
+
+/// Settings picked on the command line, e.g. `--board 5x5 --undos unlimited`.
+#[derive(Debug, Default)]
+pub struct Options {
+    pub board: BoardConfig,
+    pub undo: UndoConfig,
+    pub rng: RngConfig,
+    pub win: WinConfig,
+    pub search: SearchConfig,
+    pub input_buffer: InputBufferConfig,
+    pub animation: AnimationConfig,
+    pub gesture: GestureConfig,
+    pub theme: Theme,
+    /// Replay file to play back instead of playing
+    pub replay: Option<PathBuf>,
+    pub playback: PlaybackConfig,
+}
+
+impl Options {
+    pub fn from_args() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(options) => options,
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    pub fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--board" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--board expects a size such as 4x4".to_string())?;
+                    options.board = value.parse()?;
+                }
+                "--undo-depth" => {
+                    options.undo.history_depth = parse_limit(&arg, args.next())?;
+                }
+                "--undos" => {
+                    options.undo.undos_per_game = parse_limit(&arg, args.next())?;
+                }
+                "--no-undo" => options.undo = UndoConfig::disabled(),
+                "--seed" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--seed expects a number".to_string())?;
+                    let seed = value
+                        .parse::<u64>()
+                        .map_err(|_| format!("Invalid seed {}", value))?;
+                    options.rng.seed = Some(seed);
+                }
+                "--target" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--target expects a block such as 2048".to_string())?;
+                    // Merges make 4 at the least, so a target of 2 could never be reached
+                    options.win.target = value
+                        .parse::<u64>()
+                        .ok()
+                        .and_then(Number::from_value)
+                        .filter(|target| target.exponent() >= 2)
+                        .ok_or_else(|| {
+                            format!("Invalid target {}, expected a power of two from 4", value)
+                        })?;
+                }
+                "--ai-depth" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--ai-depth expects a number of moves".to_string())?;
+                    options.search.depth = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|depth| *depth > 0)
+                        .ok_or_else(|| format!("Invalid search depth {}", value))?;
+                }
+                "--ai-time" => {
+                    options.search.time_budget = parse_limit(&arg, args.next())?
+                        .map(|millis| Duration::from_millis(millis as u64));
+                }
+                "--input-buffer" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--input-buffer expects a number of moves".to_string())?;
+                    options.input_buffer.depth = value
+                        .parse::<usize>()
+                        .map_err(|_| format!("Invalid input buffer depth {}", value))?;
+                }
+                "--fast-forward" => options.input_buffer.fast_forward = true,
+                "--animation-speed" => {
+                    let value = args.next().ok_or_else(|| {
+                        "--animation-speed expects a factor such as 2".to_string()
+                    })?;
+                    options.animation.speed = value
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|speed| *speed > 0.0)
+                        .ok_or_else(|| format!("Invalid animation speed {}", value))?;
+                }
+                "--easing" => {
+                    let value = args.next().ok_or_else(|| {
+                        "--easing expects linear, ease-out-cubic or back".to_string()
+                    })?;
+                    options.animation.easing = Easing::parse(&value)?;
+                }
+                "--swipe-distance" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--swipe-distance expects a number of pixels".to_string())?;
+                    options.gesture.min_distance = value
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|distance| *distance > 0.0)
+                        .ok_or_else(|| format!("Invalid swipe distance {}", value))?;
+                }
+                "--swipe-angle" => {
+                    let value = args.next().ok_or_else(|| {
+                        "--swipe-angle expects a number of degrees up to 45".to_string()
+                    })?;
+                    options.gesture.angle_tolerance = value
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|angle| (0.0..=45.0).contains(angle))
+                        .ok_or_else(|| format!("Invalid swipe angle {}", value))?;
+                }
+                "--theme" => {
+                    let value = args.next().ok_or_else(|| {
+                        "--theme expects a theme name or a theme file".to_string()
+                    })?;
+                    options.theme = Theme::load(&value)?;
+                }
+                "--replay" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--replay expects a replay file".to_string())?;
+                    options.replay = Some(PathBuf::from(value));
+                }
+                "--replay-speed" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--replay-speed expects moves per second".to_string())?;
+                    options.playback.speed = value
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|speed| *speed > 0.0)
+                        .ok_or_else(|| format!("Invalid replay speed {}", value))?;
+                }
+                "--replay-from" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| "--replay-from expects a move number".to_string())?;
+                    options.playback.start_at = value
+                        .parse::<usize>()
+                        .map_err(|_| format!("Invalid move number {}", value))?;
+                }
+                _ => return Err(format!("Unknown argument {}", arg)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Parses a count where `unlimited` means no limit
+fn parse_limit(name: &str, value: Option<String>) -> Result<Option<usize>, String> {
+    match value.as_deref() {
+        Some("unlimited") => Ok(None),
+        Some(value) => value
+            .parse::<usize>()
+            .map(Some)
+            .map_err(|_| format!("Invalid value {} for {}", value, name)),
+        None => Err(format!("{} expects a number or unlimited", name)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn target_is_a_power_of_two_a_merge_can_make() {
+        assert_eq!(
+            parse(&["--target", "4"]).unwrap().win.target,
+            Number::from_value(4).unwrap()
+        );
+        assert_eq!(
+            parse(&["--target", "65536"]).unwrap().win.target,
+            Number::from_value(65536).unwrap()
+        );
+
+        assert!(parse(&["--target", "2"]).is_err());
+        assert!(parse(&["--target", "1"]).is_err());
+        assert!(parse(&["--target", "100"]).is_err());
+        assert!(parse(&["--target"]).is_err());
+    }
+}
@@ -0,0 +1,268 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::input::keyboard::{KeyCode, KeyboardInput};
+use bevy::input::ElementState;
+use bevy::prelude::*;
+use rules::replay::Step;
+use rules::Replay;
+
+use crate::{
+    events::{
+        BlockAdded, BoardRestored, GameOver, KeepGoingRequested, MoveRequested,
+        ReplaySaveRequested, ScoreChanged, UndoRequested,
+    },
+    logic::{self, LogicState},
+    storage,
+};
+
+const DIRECTORY: &str = "replays";
+
+/// Writes the game being played to the replay directory once it is over,
+/// or whenever the player asks for it.
+pub struct RecordingPlugin;
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_to_stage(CoreStage::Last, save_replay.system());
+    }
+}
+
+fn save_replay(
+    state: Res<LogicState>,
+    mut game_over: EventReader<GameOver>,
+    mut requested: EventReader<ReplaySaveRequested>,
+) {
+    let game_over = game_over.iter().count() > 0;
+    let requested = requested.iter().count() > 0;
+    if !game_over && !requested {
+        return;
+    }
+
+    let recording = match &state.recording {
+        Some(recording) => recording,
+        None => {
+            println!("A resumed game can't be saved as a replay");
+            return;
+        }
+    };
+
+    let name = format!("{}/{}.json", DIRECTORY, recording.seed);
+    if let Some(path) = storage::data_file(&name) {
+        let json = serde_json::to_string(recording).expect("Replay can always be serialized");
+        match storage::write_atomically(&path, &json) {
+            Ok(()) => println!("Saved replay to {}", path.display()),
+            Err(error) => println!("Could not save replay: {}", error),
+        }
+    }
+}
+
+/// Reads a replay and plays it through, failing at the first move that
+/// doesn't spawn the recorded block.
+pub fn load(path: &Path) -> Result<Replay, String> {
+    let contents = fs::read_to_string(path).map_err(|error| error.to_string())?;
+    let replay: Replay =
+        serde_json::from_str(&contents).map_err(|error| format!("corrupt replay: {}", error))?;
+    replay.verify().map_err(|error| error.to_string())?;
+    Ok(replay)
+}
+
+/// How a replay is played back, picked on the command line
+#[derive(Debug, Clone, Copy)]
+pub struct PlaybackConfig {
+    /// Moves per second
+    pub speed: f32,
+    /// Move the replay starts from
+    pub start_at: usize,
+}
+
+impl Default for PlaybackConfig {
+    fn default() -> Self {
+        Self {
+            speed: 4.0,
+            start_at: 0,
+        }
+    }
+}
+
+/// Feeds a replay through the same events as the keyboard. Space pauses,
+/// the right arrow steps one move, up and down change the speed, and
+/// typing a move number followed by return jumps to it.
+pub struct PlaybackPlugin;
+
+impl Plugin for PlaybackPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system(playback_input.system())
+            .add_system(play.system());
+    }
+}
+
+pub struct Playback {
+    replay: Replay,
+    /// Number of steps already played
+    next: usize,
+    paused: bool,
+    speed: f32,
+    since_step: f32,
+    step_requested: bool,
+    jump: Option<usize>,
+    /// Move number typed so far
+    typed: String,
+}
+
+impl Playback {
+    pub fn new(replay: Replay, config: PlaybackConfig) -> Self {
+        Self {
+            replay: replay,
+            next: 0,
+            paused: false,
+            speed: config.speed,
+            since_step: 0.0,
+            step_requested: false,
+            jump: Some(config.start_at).filter(|step| *step > 0),
+            typed: String::new(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.replay.steps.len()
+    }
+}
+
+fn digit(keycode: KeyCode) -> Option<char> {
+    use KeyCode::*;
+
+    let digit = match keycode {
+        Key0 | Numpad0 => '0',
+        Key1 | Numpad1 => '1',
+        Key2 | Numpad2 => '2',
+        Key3 | Numpad3 => '3',
+        Key4 | Numpad4 => '4',
+        Key5 | Numpad5 => '5',
+        Key6 | Numpad6 => '6',
+        Key7 | Numpad7 => '7',
+        Key8 | Numpad8 => '8',
+        Key9 | Numpad9 => '9',
+        _ => return None,
+    };
+    Some(digit)
+}
+
+fn playback_input(mut playback: ResMut<Playback>, mut input_events: EventReader<KeyboardInput>) {
+    use KeyCode::*;
+
+    for event in input_events.iter() {
+        let keycode = match event.key_code {
+            Some(keycode) if event.state == ElementState::Pressed => keycode,
+            _ => continue,
+        };
+
+        if let Some(digit) = digit(keycode) {
+            playback.typed.push(digit);
+            continue;
+        }
+
+        match keycode {
+            Space => {
+                playback.paused = !playback.paused;
+                println!(
+                    "Replay {}",
+                    if playback.paused { "paused" } else { "playing" }
+                );
+            }
+            Right => {
+                playback.paused = true;
+                playback.step_requested = true;
+            }
+            Up => playback.speed *= 2.0,
+            Down => playback.speed /= 2.0,
+            Back => {
+                playback.typed.pop();
+            }
+            Return => {
+                let typed = std::mem::take(&mut playback.typed);
+                match typed.parse::<usize>() {
+                    Ok(step) => playback.jump = Some(step.min(playback.len())),
+                    Err(_) => println!("Type a move number before return to jump to it"),
+                }
+            }
+            Home => playback.jump = Some(0),
+            End => playback.jump = Some(playback.len()),
+            _ => (),
+        }
+    }
+}
+
+fn play(
+    time: Res<Time>,
+    mut playback: ResMut<Playback>,
+    mut state: ResMut<LogicState>,
+    mut moves: EventWriter<MoveRequested>,
+    mut undos: EventWriter<UndoRequested>,
+    mut keep_going: EventWriter<KeepGoingRequested>,
+    mut restored: EventWriter<BoardRestored>,
+    mut block_added: EventWriter<BlockAdded>,
+    mut score_changed: EventWriter<ScoreChanged>,
+    mut game_over: EventWriter<GameOver>,
+) {
+    if !state.ready_for_next_move() {
+        return;
+    }
+
+    if let Some(step) = playback.jump.take() {
+        match playback.replay.play(step) {
+            Ok(game) => {
+                state.game = game;
+                state.is_won = false;
+                playback.next = step;
+                playback.since_step = 0.0;
+                logic::announce_board(
+                    &mut state,
+                    &mut restored,
+                    &mut block_added,
+                    &mut score_changed,
+                );
+                if state.game.is_game_over() {
+                    game_over.send(GameOver);
+                }
+                println!("Jumped to move {} of {}", step, playback.len());
+            }
+            Err(error) => println!("Could not jump to move {}: {}", step, error),
+        }
+        return;
+    }
+
+    // Keep going on behalf of the player when the replay goes on past a win
+    if state.is_won {
+        if playback.next < playback.len() {
+            keep_going.send(KeepGoingRequested);
+        }
+        return;
+    }
+
+    let step_requested = std::mem::take(&mut playback.step_requested);
+    if !step_requested {
+        if playback.paused {
+            return;
+        }
+        playback.since_step += time.delta_seconds();
+        if playback.since_step < 1.0 / playback.speed {
+            return;
+        }
+    }
+
+    let step = match playback.replay.steps.get(playback.next) {
+        Some(step) => *step,
+        None => return,
+    };
+    playback.next += 1;
+    playback.since_step = 0.0;
+
+    match step {
+        Step::Move { direction, .. } => moves.send(MoveRequested::new(direction)),
+        Step::Undo => undos.send(UndoRequested),
+    }
+    if playback.next == playback.len() {
+        println!("Replay finished after {} moves", playback.len());
+    }
+}
@@ -0,0 +1,295 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use rules::save::SavedGame;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    events::{
+        BlockAdded, BoardRestored, MoveRequested, ResumeClosed, ResumeDeclined, ResumeOffered,
+        ResumeRequested, ScoreChanged,
+    },
+    logic::{self, LogicState},
+    storage,
+};
+
+const FILE_NAME: &str = "savegame.json";
+
+/// Bumped whenever the layout of `SaveGame` changes, older saves are refused
+pub const SAVE_VERSION: u32 = 3;
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(SaveSlot::load(storage::data_file(FILE_NAME)))
+            .add_startup_system(offer_resume.system())
+            .add_system(resume_requested_listener.system())
+            .add_system(resume_declined_listener.system())
+            .add_system_to_stage(CoreStage::Last, autosave.system())
+            .add_system_to_stage(CoreStage::Last, save_on_exit.system());
+    }
+}
+
+/// Where the game is saved, and the previous game until the player
+/// decides whether to resume it.
+pub struct SaveSlot {
+    path: Option<PathBuf>,
+    offer: Option<SaveGame>,
+}
+
+impl SaveSlot {
+    fn load(path: Option<PathBuf>) -> Self {
+        let offer = match path.as_deref().map(SaveGame::load) {
+            Some(Ok(save)) if !save.game.is_game_over => Some(save),
+            Some(Err(LoadError::Missing)) | Some(Ok(_)) | None => None,
+            Some(Err(error)) => {
+                println!("Could not load saved game, starting a new one: {}", error);
+                None
+            }
+        };
+
+        Self { path, offer }
+    }
+
+    fn save(&self, state: &LogicState) {
+        if let Some(path) = &self.path {
+            if let Err(error) = SaveGame::capture(state).save(path) {
+                println!("Could not save game: {}", error);
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SavedVersion {
+    version: u32,
+}
+
+/// Everything needed to carry on with a game, as written to disk
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaveGame {
+    version: u32,
+    #[serde(flatten)]
+    game: SavedGame,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Missing,
+    Io(io::Error),
+    Corrupt(String),
+    UnsupportedVersion(u32),
+    Invalid(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Missing => write!(f, "no saved game"),
+            LoadError::Io(error) => write!(f, "{}", error),
+            LoadError::Corrupt(error) => write!(f, "corrupt save: {}", error),
+            LoadError::UnsupportedVersion(version) => write!(
+                f,
+                "save version {} is not supported, expected {}",
+                version, SAVE_VERSION
+            ),
+            LoadError::Invalid(error) => write!(f, "invalid save: {}", error),
+        }
+    }
+}
+
+impl SaveGame {
+    /// Captures a state between moves, a move waiting for its animation is saved as done
+    pub fn capture(state: &LogicState) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            game: state.game.to_saved(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, LoadError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::from_json(&contents),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Err(LoadError::Missing),
+            Err(error) => Err(LoadError::Io(error)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        storage::write_atomically(path, &self.to_json())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Save game can always be serialized")
+    }
+
+    pub fn from_json(contents: &str) -> Result<Self, LoadError> {
+        // Check the version on its own first, older layouts won't parse as a SaveGame
+        let saved: SavedVersion = serde_json::from_str(contents)
+            .map_err(|error| LoadError::Corrupt(error.to_string()))?;
+        if saved.version != SAVE_VERSION {
+            return Err(LoadError::UnsupportedVersion(saved.version));
+        }
+
+        serde_json::from_str(contents).map_err(|error| LoadError::Corrupt(error.to_string()))
+    }
+
+    /// Replaces the state with the saved game, leaving it untouched if the save is invalid
+    pub fn restore(self, state: &mut LogicState) -> Result<(), LoadError> {
+        state.game.restore(self.game).map_err(LoadError::Invalid)?;
+        state.is_won = false;
+        state.recording = None;
+        Ok(())
+    }
+}
+
+fn offer_resume(slot: Res<SaveSlot>, mut offered: EventWriter<ResumeOffered>) {
+    if let Some(save) = &slot.offer {
+        offered.send(ResumeOffered {
+            score: save.game.score,
+        });
+    }
+}
+
+fn resume_requested_listener(
+    mut slot: ResMut<SaveSlot>,
+    mut state: ResMut<LogicState>,
+    mut events: EventReader<ResumeRequested>,
+    mut closed: EventWriter<ResumeClosed>,
+    mut restored: EventWriter<BoardRestored>,
+    mut block_added: EventWriter<BlockAdded>,
+    mut score_changed: EventWriter<ScoreChanged>,
+) {
+    for _ in events.iter() {
+        if let Some(save) = slot.offer.take() {
+            closed.send(ResumeClosed);
+
+            if !state.ready_for_next_move() {
+                println!("Could not resume saved game during a move");
+                continue;
+            }
+
+            match save.restore(&mut state) {
+                Ok(()) => logic::announce_board(
+                    &mut state,
+                    &mut restored,
+                    &mut block_added,
+                    &mut score_changed,
+                ),
+                Err(error) => println!("Could not resume saved game: {}", error),
+            }
+        }
+    }
+}
+
+/// Playing on or declining the offer starts over with the new game
+fn resume_declined_listener(
+    mut slot: ResMut<SaveSlot>,
+    mut declined: EventReader<ResumeDeclined>,
+    mut moves: EventReader<MoveRequested>,
+    mut closed: EventWriter<ResumeClosed>,
+) {
+    let declined = declined.iter().count() > 0;
+    let moved = moves.iter().count() > 0;
+    if (declined || moved) && slot.offer.take().is_some() {
+        closed.send(ResumeClosed);
+    }
+}
+
+fn autosave(slot: Res<SaveSlot>, state: Res<LogicState>) {
+    // Keep the previous game on disk until the player chose whether to resume it
+    if state.is_changed() && state.ready_for_next_move() && slot.offer.is_none() {
+        slot.save(&state);
+    }
+}
+
+fn save_on_exit(slot: Res<SaveSlot>, state: Res<LogicState>, mut events: EventReader<AppExit>) {
+    if events.iter().count() > 0 && state.ready_for_next_move() && slot.offer.is_none() {
+        slot.save(&state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{position_map::Direction, rng::RngConfig};
+    use rules::{BoardConfig, Game, GameConfig};
+
+    fn played_game() -> LogicState {
+        let mut game = Game::new(GameConfig {
+            rng: RngConfig { seed: Some(42) },
+            ..GameConfig::default()
+        });
+        for direction in Direction::ALL.iter() {
+            game.apply(*direction);
+        }
+        LogicState::new(game)
+    }
+
+    #[test]
+    fn restored_game_matches_saved_game() {
+        let state = played_game();
+        let json = SaveGame::capture(&state).to_json();
+
+        let mut restored = LogicState::new(Game::new(GameConfig::default()));
+        SaveGame::from_json(&json)
+            .unwrap()
+            .restore(&mut restored)
+            .unwrap();
+
+        assert_eq!(
+            format!("{:?}", restored.game.board().blocks()),
+            format!("{:?}", state.game.board().blocks())
+        );
+        assert_eq!(restored.game.score(), state.game.score());
+        assert_eq!(restored.game.seed(), state.game.seed());
+        assert_eq!(SaveGame::capture(&restored).to_json(), json);
+    }
+
+    #[test]
+    fn older_version_is_refused() {
+        let state = played_game();
+        let older = SAVE_VERSION - 1;
+        let json = SaveGame::capture(&state).to_json().replace(
+            &format!("\"version\":{}", SAVE_VERSION),
+            &format!("\"version\":{}", older),
+        );
+
+        assert!(matches!(
+            SaveGame::from_json(&json),
+            Err(LoadError::UnsupportedVersion(version)) if version == older
+        ));
+    }
+
+    #[test]
+    fn corrupt_save_is_refused() {
+        assert!(matches!(
+            SaveGame::from_json(&format!("{{\"version\":{},\"blocks\":", SAVE_VERSION)),
+            Err(LoadError::Corrupt(_))
+        ));
+        assert!(matches!(
+            SaveGame::from_json("not json"),
+            Err(LoadError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn save_for_another_board_size_is_refused() {
+        let save = SaveGame::capture(&played_game());
+
+        let mut target = LogicState::new(Game::new(GameConfig {
+            board: BoardConfig::new(5, 5).unwrap(),
+            ..GameConfig::default()
+        }));
+
+        assert!(matches!(
+            save.restore(&mut target),
+            Err(LoadError::Invalid(_))
+        ));
+    }
+}
@@ -2,6 +2,8 @@ use bevy::prelude::*;
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
 pub enum CustomStage {
+    /// Nodes are moved to a new window size before anything else in the frame uses them
+    Layout,
     Before,
     After,
 }
@@ -15,6 +17,11 @@ impl Plugin for StagePlugin {
             CustomStage::Before,
             SystemStage::parallel(),
         )
+        .add_stage_before(
+            CustomStage::Before,
+            CustomStage::Layout,
+            SystemStage::parallel(),
+        )
         .add_stage_after(
             CoreStage::Update,
             CustomStage::After,
@@ -0,0 +1,53 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const DIRECTORY: &str = "bevy-2048";
+
+/// Path of a file kept in the user's data directory
+pub fn data_file(name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(DIRECTORY).join(name))
+}
+
+/// Writes to a temporary file first so a crash can't leave a half written file
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
+
+#[cfg(test)]
+pub mod test_support {
+    use super::*;
+
+    /// A directory of its own for one test, removed when the test ends
+    pub struct TestDir(PathBuf);
+
+    impl TestDir {
+        /// `prefix` names the module the test is in, keeping modules apart
+        pub fn new(prefix: &str, test: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "bevy-2048-{}-{}-{}",
+                prefix,
+                std::process::id(),
+                test
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            TestDir(dir)
+        }
+
+        pub fn file(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+}
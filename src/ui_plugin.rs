@@ -1,22 +1,40 @@
+use bevy::app::AppExit;
+use bevy::ecs::component::Component;
 use bevy::prelude::*;
+use bevy::window::{WindowId, WindowResized};
 use std::path::Path;
 
 use crate::{
-    events::{BestChanged, BlockAdded, BlocksDeleted, GameOver, GameRestarted, ScoreChanged},
+    animation::{Pop, ScorePopup},
+    app_state::AppState,
+    events::{
+        BestChanged, BlockAdded, BlocksDeleted, BlocksMoved, BoardRestored, ContinueRequested,
+        GameContinued, GameOver, GameRestarted, GameWon, HintShown, KeepGoingRequested,
+        LayoutChanged, MainMenuRequested, NewGameRequested, RestartRequested, ResumeClosed,
+        ResumeOffered, ScoreChanged, ScoreGained, ThemeSwitchRequested,
+    },
+    layout::{Layout, BOX_MARGIN, HEIGHT, WIDTH},
+    logic::{BoardConfig, LogicState},
     stages::CustomStage,
 };
 
-use self::{game_over_renderer::GameOverRoot, number_renderer::Block};
+use self::{
+    game_over_renderer::GameOverRoot,
+    hint_renderer::{HintRoot, HintStrip},
+    menu_renderer::{MenuButton, MenuRoot, PauseRoot},
+    number_renderer::{Block, RenderNumber},
+    resume_renderer::ResumeRoot,
+    theme::{Paint, Theme, ThemeChoices},
+    win_renderer::{WinButton, WinRoot},
+};
 
 pub mod game_over_renderer;
+pub mod hint_renderer;
+pub mod menu_renderer;
 pub mod number_renderer;
-
-pub const WIDTH: f32 = 480.0;
-pub const HEIGHT: f32 = 640.0;
-const CELL_SIZE: f32 = WIDTH / 5.0;
-const FIELD_SIZE: f32 = 50.0 + 4.0 * CELL_SIZE;
-const LEFT_INDENT: f32 = (WIDTH - FIELD_SIZE) / 2.0;
-const TOP_INDENT: f32 = 150.0;
+pub mod resume_renderer;
+pub mod theme;
+pub mod win_renderer;
 
 struct State {
     pub root: Entity,
@@ -42,33 +60,75 @@ impl State {
 
 pub struct UIPlugin;
 struct ScoreText;
+struct ScoreBox;
 struct BestText;
 struct Root;
 
-pub fn column_x(number: i32) -> f32 {
-    LEFT_INDENT + (10.0 + (CELL_SIZE + 10.0) * number as f32)
-}
-
-pub fn row_y(number: i32) -> f32 {
-    TOP_INDENT + (10.0 + (CELL_SIZE + 10.0) * number as f32)
-}
-
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut AppBuilder) {
+        let config = *app
+            .world_mut()
+            .get_resource_or_insert_with(BoardConfig::default);
+
+        let theme = app
+            .world_mut()
+            .get_resource_or_insert_with(Theme::default)
+            .clone();
+
+        // Laid out for the window the game opens in until it is resized
+        let (width, height) = app
+            .world_mut()
+            .get_resource::<WindowDescriptor>()
+            .map_or((WIDTH, HEIGHT), |window| (window.width, window.height));
+
         app.insert_resource(State::new())
+            .insert_resource(Layout::new(&config, width, height))
+            .insert_resource(ThemeChoices::with(&theme))
             .add_startup_system(setup.system())
+            .add_system_to_stage(CoreStage::PreUpdate, window_resized_listener.system())
+            .add_system_to_stage(CustomStage::Layout, relayout_header.system())
+            .add_system_to_stage(CustomStage::Layout, relayout_field.system())
+            .add_system_to_stage(CustomStage::Layout, relayout_blocks.system())
+            .add_system_to_stage(CustomStage::Layout, relayout_hint.system())
             .add_system_to_stage(CustomStage::Before, block_added_listener.system())
             .add_system_to_stage(CustomStage::Before, blocks_deleted_listener.system())
             .add_system_to_stage(CustomStage::Before, game_over_listener.system())
             .add_system_to_stage(CustomStage::After, score_changed_listener.system())
+            .add_system_to_stage(CustomStage::After, score_gained_listener.system())
             .add_system_to_stage(CustomStage::After, best_changed_listener.system())
-            .add_system_to_stage(CustomStage::Before, game_restarted_listener.system());
+            .add_system_to_stage(CustomStage::Before, game_restarted_listener.system())
+            .add_system_to_stage(CustomStage::Before, board_restored_listener.system())
+            .add_system_to_stage(CustomStage::Before, resume_offered_listener.system())
+            .add_system_to_stage(CustomStage::Before, resume_closed_listener.system())
+            .add_system_to_stage(CustomStage::Before, game_won_listener.system())
+            .add_system_to_stage(CustomStage::Before, game_continued_listener.system())
+            .add_system_to_stage(CustomStage::Before, hint_shown_listener.system())
+            .add_system_to_stage(CustomStage::Before, hint_cleared_listener.system())
+            .add_system(win_button_system.system())
+            .add_system(menu_button_system.system())
+            .add_system(theme_switch_requested_listener.system())
+            .add_system_to_stage(CoreStage::PostUpdate, theme::recolor.system())
+            .add_system_set(
+                SystemSet::on_enter(AppState::MainMenu).with_system(main_menu_entered.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::MainMenu)
+                    .with_system(despawn_all::<MenuRoot>.system()),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Paused).with_system(paused_entered.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Paused).with_system(despawn_all::<PauseRoot>.system()),
+            );
     }
 }
 
 fn setup(
     mut commands: Commands,
     mut state: ResMut<State>,
+    layout: Res<Layout>,
+    theme: Res<Theme>,
     asset_server: Res<AssetServer>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
@@ -77,21 +137,27 @@ fn setup(
     let font: Handle<Font> = asset_server.load(Path::new("fonts").join("FiraSans-Bold.ttf"));
     state.set_font(&font);
 
-    let bg_color = materials.add(Color::rgb(0.725, 0.675, 0.627).into());
-    let bg_cell_color = materials.add(Color::rgb(0.808, 0.753, 0.698).into());
+    let bg_color = materials.add(theme.field.into());
+    let bg_cell_color = materials.add(theme.empty_cell.into());
 
     let root = commands
-        .spawn_bundle(root(&mut materials))
+        .spawn_bundle(root(&mut materials, &theme))
         .insert(Root)
+        .insert(Paint::Background)
         .with_children(|parent| {
-            build_header(parent, &mut materials, font.clone());
+            build_header(parent, &mut materials, font.clone(), &layout, &theme);
 
             parent
-                .spawn_bundle(background_field(bg_color))
+                .spawn_bundle(background_field(&layout, bg_color))
+                .insert(Field)
+                .insert(Paint::Field)
                 .with_children(|parent| {
-                    for row in 0..4 {
-                        for col in 0..4 {
-                            parent.spawn_bundle(bg_cell(row, col, bg_cell_color.clone()));
+                    for row in 0..layout.rows {
+                        for col in 0..layout.columns {
+                            parent
+                                .spawn_bundle(bg_cell(&layout, row, col, bg_cell_color.clone()))
+                                .insert(EmptyCell { row: row, col: col })
+                                .insert(Paint::EmptyCell);
                         }
                     }
                 });
@@ -112,6 +178,56 @@ fn score_changed_listener(
     }
 }
 
+/// Floats the points of a move up from the score box
+fn score_gained_listener(
+    mut commands: Commands,
+    state: ResMut<State>,
+    layout: Res<Layout>,
+    theme: Res<Theme>,
+    mut events: EventReader<ScoreGained>,
+    score_boxes: Query<Entity, With<ScoreBox>>,
+    popups: Query<&ScorePopup>,
+) {
+    let mut lowest = popups.iter().map(|popup| popup.rise()).reduce(f32::min);
+
+    for event in events.iter() {
+        let popup = ScorePopup::below(lowest);
+        lowest = Some(popup.rise());
+
+        for score_box in score_boxes.iter() {
+            let text = TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: Rect {
+                        top: Val::Px(-popup.rise()),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                text: Text::with_section(
+                    format!("+{}", event.points),
+                    TextStyle {
+                        font: state.font(),
+                        font_size: layout.header_cell_size * 0.3,
+                        color: theme.popup_text,
+                    },
+                    TextAlignment {
+                        vertical: VerticalAlign::Center,
+                        horizontal: HorizontalAlign::Center,
+                    },
+                ),
+                ..Default::default()
+            };
+            commands.entity(score_box).with_children(|parent| {
+                parent
+                    .spawn_bundle(text)
+                    .insert(popup)
+                    .insert(Paint::PopupText);
+            });
+        }
+    }
+}
+
 fn best_changed_listener(
     mut events: EventReader<BestChanged>,
     mut query: Query<&mut Text, With<BestText>>,
@@ -126,6 +242,8 @@ fn best_changed_listener(
 fn block_added_listener(
     mut commands: Commands,
     state: ResMut<State>,
+    layout: Res<Layout>,
+    theme: Res<Theme>,
     mut events: EventReader<BlockAdded>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
@@ -134,18 +252,30 @@ fn block_added_listener(
             "New blocked added with id: {}, number: {:?}, position: {:?}",
             event.id, event.number, event.position
         );
+        let cell = (
+            layout.column_x(event.position.x),
+            layout.row_y(event.position.y),
+        );
+        let mut block = None;
         commands.entity(state.root).with_children(|parent| {
-            event.number.render(
+            block = Some(event.number.render(
                 event.id,
-                (
-                    column_x(event.position.x.try_into().unwrap()),
-                    row_y(event.position.y.try_into().unwrap()),
-                ),
+                cell,
+                layout.cell_size,
                 parent,
                 &mut materials,
                 state.font(),
-            );
+                &theme,
+            ));
         });
+
+        let font_size = event.number.text_size(layout.cell_size);
+        if let (Some(block), Some(pop)) = (
+            block,
+            Pop::new(event.origin, cell, layout.cell_size, font_size),
+        ) {
+            commands.entity(block).insert(pop);
+        }
     }
 }
 
@@ -166,22 +296,305 @@ fn blocks_deleted_listener(
 fn game_over_listener(
     mut commands: Commands,
     state: ResMut<State>,
+    logic: Res<LogicState>,
+    theme: Res<Theme>,
     mut events: EventReader<GameOver>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
     for _ in events.iter() {
         println!("Rendering game over screen!");
         commands.entity(state.root).with_children(|parent| {
-            game_over_renderer::render(parent, &mut materials, state.font())
+            game_over_renderer::render(
+                parent,
+                &mut materials,
+                state.font(),
+                &theme,
+                logic.game.seed(),
+            )
+        });
+    }
+}
+
+fn game_won_listener(
+    mut commands: Commands,
+    state: ResMut<State>,
+    theme: Res<Theme>,
+    mut events: EventReader<GameWon>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for _ in events.iter() {
+        commands.entity(state.root).with_children(|parent| {
+            win_renderer::render(parent, &mut materials, state.font(), &theme)
+        });
+    }
+}
+
+fn game_continued_listener(
+    mut commands: Commands,
+    mut events: EventReader<GameContinued>,
+    win_entities: Query<Entity, With<WinRoot>>,
+) {
+    for _ in events.iter() {
+        for win_root in win_entities.iter() {
+            commands.entity(win_root).despawn_recursive();
+        }
+    }
+}
+
+fn win_button_system(
+    query: Query<(&Interaction, &WinButton), Changed<Interaction>>,
+    mut keep_going: EventWriter<KeepGoingRequested>,
+    mut restart: EventWriter<RestartRequested>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction == Interaction::Clicked {
+            match button {
+                WinButton::KeepGoing => keep_going.send(KeepGoingRequested),
+                WinButton::NewGame => restart.send(RestartRequested),
+            }
+        }
+    }
+}
+
+fn menu_button_system(
+    query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut new_game: EventWriter<NewGameRequested>,
+    mut continue_game: EventWriter<ContinueRequested>,
+    mut main_menu: EventWriter<MainMenuRequested>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for (interaction, button) in query.iter() {
+        if *interaction == Interaction::Clicked {
+            match button {
+                MenuButton::NewGame => new_game.send(NewGameRequested),
+                MenuButton::Continue => continue_game.send(ContinueRequested),
+                MenuButton::MainMenu => main_menu.send(MainMenuRequested),
+                MenuButton::Quit => exit.send(AppExit),
+            }
+        }
+    }
+}
+
+fn main_menu_entered(
+    mut commands: Commands,
+    state: ResMut<State>,
+    theme: Res<Theme>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.entity(state.root).with_children(|parent| {
+        menu_renderer::render_main_menu(parent, &mut materials, state.font(), &theme)
+    });
+}
+
+fn paused_entered(
+    mut commands: Commands,
+    state: ResMut<State>,
+    theme: Res<Theme>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.entity(state.root).with_children(|parent| {
+        menu_renderer::render_pause(parent, &mut materials, state.font(), &theme)
+    });
+}
+
+/// Moves on to the next theme, existing nodes are repainted by `theme::recolor`
+fn theme_switch_requested_listener(
+    mut theme: ResMut<Theme>,
+    choices: Res<ThemeChoices>,
+    mut events: EventReader<ThemeSwitchRequested>,
+) {
+    for _ in events.iter() {
+        *theme = theme.next(&choices.themes).clone();
+        println!("Switched to the {} theme", theme.name);
+    }
+}
+
+/// Lays the game out again for the new size of the window
+fn window_resized_listener(
+    config: Res<BoardConfig>,
+    mut layout: ResMut<Layout>,
+    mut events: EventReader<WindowResized>,
+    mut changed: EventWriter<LayoutChanged>,
+) {
+    let resized = events.iter().rfind(|event| event.id == WindowId::primary());
+    if let Some(event) = resized {
+        let resized = Layout::new(&config, event.width, event.height);
+        if resized != *layout {
+            changed.send(LayoutChanged { previous: *layout });
+            *layout = resized;
+        }
+    }
+}
+
+fn relayout_header(
+    layout: Res<Layout>,
+    mut events: EventReader<LayoutChanged>,
+    mut parts: Query<(&HeaderPart, &mut Style)>,
+    mut texts: Query<(&HeaderFont, &mut Text)>,
+) {
+    if events.iter().count() == 0 {
+        return;
+    }
+
+    for (part, mut style) in parts.iter_mut() {
+        *style = part.style(&layout);
+    }
+    for (font, mut text) in texts.iter_mut() {
+        text.sections[0].style.font_size = layout.header_cell_size * font.0;
+    }
+}
+
+fn relayout_field(
+    layout: Res<Layout>,
+    mut events: EventReader<LayoutChanged>,
+    mut fields: Query<&mut Style, (With<Field>, Without<EmptyCell>)>,
+    mut cells: Query<(&EmptyCell, &mut Style), Without<Field>>,
+) {
+    if events.iter().count() == 0 {
+        return;
+    }
+
+    for mut style in fields.iter_mut() {
+        *style = field_style(&layout);
+    }
+    for (cell, mut style) in cells.iter_mut() {
+        *style = cell_style(&layout, cell.row, cell.col);
+    }
+}
+
+/// Moves every block onto the same cell of the new layout, the animations
+/// running on them follow in `animation`
+fn relayout_blocks(
+    layout: Res<Layout>,
+    mut events: EventReader<LayoutChanged>,
+    mut blocks: Query<(&Block, &mut Style, &Children)>,
+    mut texts: Query<&mut Text>,
+) {
+    for event in events.iter() {
+        for (block, mut style, children) in blocks.iter_mut() {
+            if let (Val::Px(left), Val::Px(top)) = (style.position.left, style.position.top) {
+                let (left, top) = layout.moved_from(&event.previous, (left, top));
+                style.position.left = Val::Px(left);
+                style.position.top = Val::Px(top);
+            }
+            style.size = Size::new(Val::Px(layout.cell_size), Val::Px(layout.cell_size));
+
+            for child in children.iter() {
+                if let Ok(mut text) = texts.get_mut(*child) {
+                    text.sections[0].style.font_size = block.number.text_size(layout.cell_size);
+                }
+            }
+        }
+    }
+}
+
+fn relayout_hint(
+    layout: Res<Layout>,
+    mut events: EventReader<LayoutChanged>,
+    mut roots: Query<&mut Style, (With<HintRoot>, Without<HintStrip>)>,
+    mut strips: Query<(&HintStrip, &mut Style, &Children), Without<HintRoot>>,
+    mut texts: Query<&mut Text>,
+) {
+    if events.iter().count() == 0 {
+        return;
+    }
+
+    for mut style in roots.iter_mut() {
+        *style = hint_renderer::root_style(&layout);
+    }
+    for (strip, mut style, children) in strips.iter_mut() {
+        *style = hint_renderer::strip_style(&layout, strip);
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                text.sections[0].style.font_size = hint_renderer::font_size(&layout);
+            }
+        }
+    }
+}
+
+fn despawn_all<T: Component>(mut commands: Commands, roots: Query<Entity, With<T>>) {
+    for root in roots.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+}
+
+fn hint_shown_listener(
+    mut commands: Commands,
+    state: ResMut<State>,
+    layout: Res<Layout>,
+    theme: Res<Theme>,
+    mut events: EventReader<HintShown>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    hint_entities: Query<Entity, With<HintRoot>>,
+) {
+    for event in events.iter() {
+        for hint_root in hint_entities.iter() {
+            commands.entity(hint_root).despawn_recursive();
+        }
+
+        commands.entity(state.root).with_children(|parent| {
+            hint_renderer::render(
+                parent,
+                &mut materials,
+                state.font(),
+                &theme,
+                &layout,
+                &event.scores,
+            )
+        });
+    }
+}
+
+/// A hint only holds until the board changes
+fn hint_cleared_listener(
+    mut commands: Commands,
+    mut moved: EventReader<BlocksMoved>,
+    mut restored: EventReader<BoardRestored>,
+    mut restarted: EventReader<GameRestarted>,
+    hint_entities: Query<Entity, With<HintRoot>>,
+) {
+    let moved = moved.iter().count() > 0;
+    let restored = restored.iter().count() > 0;
+    let restarted = restarted.iter().count() > 0;
+    if moved || restored || restarted {
+        for hint_root in hint_entities.iter() {
+            commands.entity(hint_root).despawn_recursive();
+        }
+    }
+}
+
+fn resume_offered_listener(
+    mut commands: Commands,
+    state: ResMut<State>,
+    theme: Res<Theme>,
+    mut events: EventReader<ResumeOffered>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for event in events.iter() {
+        commands.entity(state.root).with_children(|parent| {
+            resume_renderer::render(parent, &mut materials, state.font(), &theme, event.score)
         });
     }
 }
 
+fn resume_closed_listener(
+    mut commands: Commands,
+    mut events: EventReader<ResumeClosed>,
+    resume_entities: Query<Entity, With<ResumeRoot>>,
+) {
+    for _ in events.iter() {
+        for resume_root in resume_entities.iter() {
+            commands.entity(resume_root).despawn_recursive();
+        }
+    }
+}
+
 fn game_restarted_listener(
     mut commands: Commands,
     mut events: EventReader<GameRestarted>,
     block_entities: Query<Entity, With<Block>>,
     game_over_entities: Query<Entity, With<GameOverRoot>>,
+    win_entities: Query<Entity, With<WinRoot>>,
 ) {
     for _ in events.iter() {
         for block in block_entities.iter() {
@@ -191,239 +604,344 @@ fn game_restarted_listener(
         for game_over_root in game_over_entities.iter() {
             commands.entity(game_over_root).despawn_recursive();
         }
+
+        for win_root in win_entities.iter() {
+            commands.entity(win_root).despawn_recursive();
+        }
     }
 }
 
-fn root(materials: &mut ResMut<Assets<ColorMaterial>>) -> NodeBundle {
+fn board_restored_listener(
+    mut commands: Commands,
+    mut events: EventReader<BoardRestored>,
+    block_entities: Query<Entity, With<Block>>,
+    game_over_entities: Query<Entity, With<GameOverRoot>>,
+    win_entities: Query<Entity, With<WinRoot>>,
+) {
+    // Blocks of the restored board arrive as BlockAdded events in the same frame,
+    // they are only spawned once this stage ends so the queries can't see them
+    for _ in events.iter() {
+        for block in block_entities.iter() {
+            commands.entity(block).despawn_recursive();
+        }
+
+        for game_over_root in game_over_entities.iter() {
+            commands.entity(game_over_root).despawn_recursive();
+        }
+
+        for win_root in win_entities.iter() {
+            commands.entity(win_root).despawn_recursive();
+        }
+    }
+}
+
+fn root(materials: &mut ResMut<Assets<ColorMaterial>>, theme: &Theme) -> NodeBundle {
     NodeBundle {
         style: Style {
-            size: Size::new(Val::Px(WIDTH), Val::Px(HEIGHT)),
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
             flex_direction: FlexDirection::Column,
             justify_content: JustifyContent::FlexStart,
             ..Default::default()
         },
-        material: materials.add(Color::rgb(0.992, 0.969, 0.941).into()),
+        material: materials.add(theme.background.into()),
         ..Default::default()
     }
 }
 
-fn build_header(
-    parent: &mut ChildBuilder,
-    materials: &mut ResMut<Assets<ColorMaterial>>,
-    font: Handle<Font>,
-) {
-    let no_color = materials.add(Color::NONE.into());
-    let logo_color = materials.add(Color::rgb(0.929, 0.778, 0.012).into());
-    let bg_color = materials.add(Color::rgb(0.725, 0.675, 0.627).into());
+/// Nodes of the header, styled from the layout so they follow the window size
+#[derive(Debug, Clone, Copy)]
+enum HeaderPart {
+    Wrapper,
+    Logo,
+    Scores,
+    ScoreBox,
+    BestBox,
+}
 
-    parent
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                size: Size::new(Val::Percent(100.0), Val::Px(CELL_SIZE + 20.0)),
-                padding: Rect {
-                    left: Val::Px(LEFT_INDENT),
-                    right: Val::Px(LEFT_INDENT),
-                    top: Val::Px(LEFT_INDENT),
-                    bottom: Val::Px(LEFT_INDENT),
-                },
+impl HeaderPart {
+    fn style(&self, layout: &Layout) -> Style {
+        let cell = layout.header_cell_size;
+        let indent = layout.header_indent;
+
+        match (self, layout.landscape) {
+            (HeaderPart::Wrapper, false) => Style {
+                size: Size::new(Val::Percent(100.0), Val::Px(cell + 20.0)),
+                padding: Rect::all(Val::Px(indent)),
                 position_type: PositionType::Absolute,
                 position: Rect {
                     left: Val::Px(0.0),
-                    top: Val::Px(LEFT_INDENT),
+                    top: Val::Px(indent),
                     ..Default::default()
                 },
                 justify_content: JustifyContent::SpaceBetween,
                 ..Default::default()
             },
-            material: no_color,
+            // A column down the left side, from the top
+            (HeaderPart::Wrapper, true) => Style {
+                size: Size::new(
+                    Val::Px(Layout::header_width(cell, indent)),
+                    Val::Percent(100.0),
+                ),
+                padding: Rect::all(Val::Px(indent)),
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(0.0),
+                    top: Val::Px(0.0),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::FlexStart,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            (HeaderPart::Logo, _) => Style {
+                size: Size::new(Val::Px(cell), Val::Px(cell)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            (HeaderPart::Scores, false) => Style {
+                size: Size::new(Val::Percent(100.0), Val::Px(cell)),
+                align_items: AlignItems::FlexEnd,
+                justify_content: JustifyContent::FlexEnd,
+                ..Default::default()
+            },
+            (HeaderPart::Scores, true) => Style {
+                margin: Rect {
+                    top: Val::Px(indent),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            (HeaderPart::ScoreBox, _) | (HeaderPart::BestBox, _) => {
+                let margin = match (self, layout.landscape) {
+                    (_, true) => Rect {
+                        top: Val::Px(BOX_MARGIN),
+                        ..Default::default()
+                    },
+                    (HeaderPart::ScoreBox, false) => Rect {
+                        left: Val::Px(BOX_MARGIN),
+                        right: Val::Px(BOX_MARGIN),
+                        ..Default::default()
+                    },
+                    _ => Rect {
+                        left: Val::Px(BOX_MARGIN),
+                        ..Default::default()
+                    },
+                };
+                let padding = match self {
+                    HeaderPart::ScoreBox => Rect::all(Val::Px(5.0)),
+                    _ => Rect::default(),
+                };
+
+                Style {
+                    size: Size::new(Val::Px(cell * 1.5), Val::Px(cell * 0.8)),
+                    padding: padding,
+                    margin: margin,
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                }
+            }
+        }
+    }
+}
+
+/// Font size of a header text, in header cells
+struct HeaderFont(f32);
+
+fn header_text(
+    label: &str,
+    size: f32,
+    vertical: VerticalAlign,
+    layout: &Layout,
+    font: Handle<Font>,
+    theme: &Theme,
+) -> TextBundle {
+    TextBundle {
+        text: Text::with_section(
+            label,
+            TextStyle {
+                font: font,
+                font_size: layout.header_cell_size * size,
+                color: theme.header_text,
+            },
+            TextAlignment {
+                vertical: vertical,
+                horizontal: HorizontalAlign::Center,
+            },
+        ),
+        ..Default::default()
+    }
+}
+
+fn build_header(
+    parent: &mut ChildBuilder,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    font: Handle<Font>,
+    layout: &Layout,
+    theme: &Theme,
+) {
+    let no_color = materials.add(Color::NONE.into());
+    let logo_color = materials.add(theme.logo.into());
+    let bg_color = materials.add(theme.score_box.into());
+
+    parent
+        .spawn_bundle(NodeBundle {
+            style: HeaderPart::Wrapper.style(layout),
+            material: no_color.clone(),
             ..Default::default()
         })
+        .insert(HeaderPart::Wrapper)
         .with_children(|wrapper| {
             wrapper
                 .spawn_bundle(NodeBundle {
-                    style: Style {
-                        size: Size::new(Val::Px(CELL_SIZE), Val::Px(CELL_SIZE)),
-                        justify_content: JustifyContent::Center,
-                        align_items: AlignItems::Center,
-                        ..Default::default()
-                    },
+                    style: HeaderPart::Logo.style(layout),
                     material: logo_color,
                     ..Default::default()
                 })
+                .insert(HeaderPart::Logo)
+                .insert(Paint::Logo)
                 .with_children(|logo| {
-                    logo.spawn_bundle(TextBundle {
-                        text: Text::with_section(
-                            "2048",
-                            TextStyle {
-                                font: font.clone(),
-                                font_size: (CELL_SIZE * 0.5),
-                                color: Color::WHITE,
-                            },
-                            TextAlignment {
-                                vertical: VerticalAlign::Center,
-                                horizontal: HorizontalAlign::Center,
-                            },
-                        ),
-                        ..Default::default()
-                    });
+                    logo.spawn_bundle(header_text(
+                        "2048",
+                        0.5,
+                        VerticalAlign::Center,
+                        layout,
+                        font.clone(),
+                        theme,
+                    ))
+                    .insert(HeaderFont(0.5))
+                    .insert(Paint::HeaderText);
                 });
             wrapper
                 .spawn_bundle(NodeBundle {
-                    style: Style {
-                        size: Size::new(Val::Percent(100.0), Val::Px(CELL_SIZE)),
-                        align_items: AlignItems::FlexEnd,
-                        justify_content: JustifyContent::FlexEnd,
-                        ..Default::default()
-                    },
-                    material: materials.add(Color::NONE.into()),
+                    style: HeaderPart::Scores.style(layout),
+                    material: no_color,
                     ..Default::default()
                 })
+                .insert(HeaderPart::Scores)
                 .with_children(|right_wrapper| {
-                    build_score(right_wrapper, bg_color.clone(), font.clone());
-                    build_best(right_wrapper, bg_color, font.clone());
+                    build_score_box(
+                        right_wrapper,
+                        HeaderPart::ScoreBox,
+                        "Score",
+                        bg_color.clone(),
+                        font.clone(),
+                        layout,
+                        theme,
+                    );
+                    build_score_box(
+                        right_wrapper,
+                        HeaderPart::BestBox,
+                        "Best",
+                        bg_color,
+                        font.clone(),
+                        layout,
+                        theme,
+                    );
                 });
         });
 }
 
-fn build_score(parent: &mut ChildBuilder, bg_color: Handle<ColorMaterial>, font: Handle<Font>) {
-    parent
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                size: Size::new(Val::Px(CELL_SIZE * 1.5), Val::Px(CELL_SIZE * 0.8)),
-                padding: Rect::all(Val::Px(5.0)),
-                margin: Rect {
-                    left: Val::Px(5.0),
-                    right: Val::Px(5.0),
-                    ..Default::default()
-                },
-                flex_direction: FlexDirection::Column,
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                ..Default::default()
-            },
-            material: bg_color,
-            ..Default::default()
-        })
-        .with_children(|bg| {
-            bg.spawn_bundle(TextBundle {
-                text: Text::with_section(
-                    "0",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: CELL_SIZE * 0.5,
-                        color: Color::WHITE,
-                    },
-                    TextAlignment {
-                        vertical: VerticalAlign::Center,
-                        horizontal: HorizontalAlign::Center,
-                    },
-                ),
-                ..Default::default()
-            })
-            .insert(ScoreText);
+/// The score or the best score with its label, `part` tells which
+fn build_score_box(
+    parent: &mut ChildBuilder,
+    part: HeaderPart,
+    label: &str,
+    bg_color: Handle<ColorMaterial>,
+    font: Handle<Font>,
+    layout: &Layout,
+    theme: &Theme,
+) {
+    let mut score_box = parent.spawn_bundle(NodeBundle {
+        style: part.style(layout),
+        material: bg_color,
+        ..Default::default()
+    });
+    score_box.insert(part).insert(Paint::ScoreBox);
+    if let HeaderPart::ScoreBox = part {
+        score_box.insert(ScoreBox);
+    }
 
-            bg.spawn_bundle(TextBundle {
-                text: Text::with_section(
-                    "Score",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: CELL_SIZE * 0.25,
-                        color: Color::WHITE,
-                    },
-                    TextAlignment {
-                        vertical: VerticalAlign::Top,
-                        horizontal: HorizontalAlign::Center,
-                    },
-                ),
-                ..Default::default()
-            });
-        });
+    score_box.with_children(|bg| {
+        let mut value = bg.spawn_bundle(header_text(
+            "0",
+            0.5,
+            VerticalAlign::Center,
+            layout,
+            font.clone(),
+            theme,
+        ));
+        value.insert(HeaderFont(0.5)).insert(Paint::HeaderText);
+        match part {
+            HeaderPart::ScoreBox => value.insert(ScoreText),
+            _ => value.insert(BestText),
+        };
+
+        bg.spawn_bundle(header_text(
+            label,
+            0.25,
+            VerticalAlign::Top,
+            layout,
+            font.clone(),
+            theme,
+        ))
+        .insert(HeaderFont(0.25))
+        .insert(Paint::HeaderText);
+    });
 }
 
-fn build_best(parent: &mut ChildBuilder, bg_color: Handle<ColorMaterial>, font: Handle<Font>) {
-    parent
-        .spawn_bundle(NodeBundle {
-            style: Style {
-                size: Size::new(Val::Px(CELL_SIZE * 1.5), Val::Px(CELL_SIZE * 0.8)),
-                margin: Rect {
-                    left: Val::Px(5.0),
-                    ..Default::default()
-                },
-                flex_direction: FlexDirection::Column,
-                justify_content: JustifyContent::Center,
-                align_items: AlignItems::Center,
-                ..Default::default()
-            },
-            material: bg_color,
-            ..Default::default()
-        })
-        .with_children(|bg| {
-            bg.spawn_bundle(TextBundle {
-                text: Text::with_section(
-                    "0",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: CELL_SIZE * 0.5,
-                        color: Color::WHITE,
-                    },
-                    TextAlignment {
-                        vertical: VerticalAlign::Center,
-                        horizontal: HorizontalAlign::Center,
-                    },
-                ),
-                ..Default::default()
-            })
-            .insert(BestText);
+/// The field and its empty cells
+struct Field;
 
-            bg.spawn_bundle(TextBundle {
-                text: Text::with_section(
-                    "Best",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: CELL_SIZE * 0.25,
-                        color: Color::WHITE,
-                    },
-                    TextAlignment {
-                        vertical: VerticalAlign::Top,
-                        horizontal: HorizontalAlign::Center,
-                    },
-                ),
-                ..Default::default()
-            });
-        });
+struct EmptyCell {
+    row: i32,
+    col: i32,
 }
 
-fn background_field(color: Handle<ColorMaterial>) -> NodeBundle {
-    NodeBundle {
-        style: Style {
-            size: Size::new(Val::Px(FIELD_SIZE), Val::Px(FIELD_SIZE)),
-            border: Rect::all(Val::Px(1.0)),
-            position_type: PositionType::Absolute,
-            position: Rect {
-                left: Val::Px(LEFT_INDENT),
-                top: Val::Px(TOP_INDENT),
-                ..Default::default()
-            },
+fn field_style(layout: &Layout) -> Style {
+    Style {
+        size: Size::new(Val::Px(layout.field_width), Val::Px(layout.field_height)),
+        border: Rect::all(Val::Px(1.0)),
+        position_type: PositionType::Absolute,
+        position: Rect {
+            left: Val::Px(layout.left_indent),
+            top: Val::Px(layout.top_indent),
             ..Default::default()
         },
-        material: color,
         ..Default::default()
     }
 }
 
-fn bg_cell(row: i32, col: i32, color: Handle<ColorMaterial>) -> NodeBundle {
-    NodeBundle {
-        style: Style {
-            size: Size::new(Val::Px(CELL_SIZE), Val::Px(CELL_SIZE)),
-            border: Rect::all(Val::Px(1.0)),
-            position_type: PositionType::Absolute,
-            position: Rect {
-                left: Val::Px((10 + (10 + CELL_SIZE as i32) * col) as f32),
-                top: Val::Px((10 + (10 + CELL_SIZE as i32) * row) as f32),
-                ..Default::default()
-            },
+fn cell_style(layout: &Layout, row: i32, col: i32) -> Style {
+    Style {
+        size: Size::new(Val::Px(layout.cell_size), Val::Px(layout.cell_size)),
+        border: Rect::all(Val::Px(1.0)),
+        position_type: PositionType::Absolute,
+        position: Rect {
+            left: Val::Px(layout.cell_offset(col)),
+            top: Val::Px(layout.cell_offset(row)),
             ..Default::default()
         },
+        ..Default::default()
+    }
+}
+
+fn background_field(layout: &Layout, color: Handle<ColorMaterial>) -> NodeBundle {
+    NodeBundle {
+        style: field_style(layout),
+        material: color,
+        ..Default::default()
+    }
+}
+
+fn bg_cell(layout: &Layout, row: i32, col: i32, color: Handle<ColorMaterial>) -> NodeBundle {
+    NodeBundle {
+        style: cell_style(layout, row, col),
         material: color,
         ..Default::default()
     }
@@ -1,4 +1,4 @@
-use crate::ui_plugin::{HEIGHT, WIDTH};
+use crate::ui_plugin::theme::{Paint, Theme};
 use bevy::prelude::*;
 
 pub struct GameOverRoot;
@@ -7,35 +7,58 @@ pub fn render(
     parent: &mut ChildBuilder,
     materials: &mut ResMut<Assets<ColorMaterial>>,
     font: Handle<Font>,
+    theme: &Theme,
+    seed: u64,
 ) {
     parent
         .spawn_bundle(NodeBundle {
             style: Style {
-                size: Size::new(Val::Px(WIDTH), Val::Px(HEIGHT)),
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                 position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 ..Default::default()
             },
-            material: materials.add(Color::rgba_u8(0, 0, 0, 180).into()),
+            material: materials.add(theme.overlay.into()),
             ..Default::default()
         })
+        .insert(Paint::Overlay)
         .insert(GameOverRoot)
         .with_children(|parent| {
-            parent.spawn_bundle(TextBundle {
-                text: Text::with_section(
-                    "Game Over!",
-                    TextStyle {
-                        font: font.clone(),
-                        font_size: 60.0,
-                        color: Color::WHITE,
-                    },
-                    TextAlignment {
-                        vertical: VerticalAlign::Center,
-                        horizontal: HorizontalAlign::Center,
-                    },
-                ),
-                ..Default::default()
-            });
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "Game Over!",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 60.0,
+                            color: theme.overlay_text,
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    ..Default::default()
+                })
+                .insert(Paint::OverlayText);
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        format!("Seed: {}", seed),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 20.0,
+                            color: theme.overlay_text,
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    ..Default::default()
+                })
+                .insert(Paint::OverlayText);
         });
 }
@@ -0,0 +1,181 @@
+use crate::layout::Layout;
+use crate::logic::position_map::Direction;
+use crate::ui_plugin::theme::{Paint, Theme};
+use bevy::prelude::*;
+use rules::ai::{self, MoveScore};
+
+pub struct HintRoot;
+/// The strip of a move, along the edge of the field it goes towards
+pub struct HintStrip(Direction);
+
+/// Thickness of the strip along each edge of the field, in cells
+const STRIP_SIZE: f32 = 0.45;
+/// Size of the labels, relative to the thickness of the strips
+const FONT_SIZE: f32 = 0.4;
+
+fn name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::LEFT => "Left",
+        Direction::RIGHT => "Right",
+        Direction::TOP => "Up",
+        Direction::BOTTOM => "Down",
+    }
+}
+
+/// How far a move falls behind the best one. The side strips are too narrow
+/// for the name of the move, where they are says which move they stand for.
+fn label(direction: Direction, is_best: bool, behind: Option<f64>) -> String {
+    let value = match behind {
+        Some(_) if is_best => "best".to_string(),
+        Some(behind) => format!("{:.0}", behind),
+        None => "-".to_string(),
+    };
+
+    match direction {
+        Direction::LEFT | Direction::RIGHT => value,
+        Direction::TOP | Direction::BOTTOM => format!("{} {}", name(direction), value),
+    }
+}
+
+fn thickness(layout: &Layout) -> f32 {
+    layout.cell_size * STRIP_SIZE
+}
+
+pub fn font_size(layout: &Layout) -> f32 {
+    thickness(layout) * FONT_SIZE
+}
+
+/// Covers the field
+pub fn root_style(layout: &Layout) -> Style {
+    Style {
+        size: Size::new(Val::Px(layout.field_width), Val::Px(layout.field_height)),
+        position_type: PositionType::Absolute,
+        position: Rect {
+            left: Val::Px(layout.left_indent),
+            top: Val::Px(layout.top_indent),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+pub fn strip_style(layout: &Layout, strip: &HintStrip) -> Style {
+    let (size, position) = self::strip(layout, strip.0);
+    Style {
+        size: size,
+        position_type: PositionType::Absolute,
+        position: position,
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..Default::default()
+    }
+}
+
+/// Strip along the edge of the field a move goes towards
+fn strip(layout: &Layout, direction: Direction) -> (Size<Val>, Rect<Val>) {
+    let (width, height) = (layout.field_width, layout.field_height);
+    let thickness = thickness(layout);
+    let (size, left, top) = match direction {
+        Direction::LEFT => ((thickness, height), 0.0, 0.0),
+        Direction::RIGHT => ((thickness, height), width - thickness, 0.0),
+        Direction::TOP => ((width, thickness), 0.0, 0.0),
+        Direction::BOTTOM => ((width, thickness), 0.0, height - thickness),
+    };
+
+    (
+        Size::new(Val::Px(size.0), Val::Px(size.1)),
+        Rect {
+            left: Val::Px(left),
+            top: Val::Px(top),
+            ..Default::default()
+        },
+    )
+}
+
+/// Marks the edge of the suggested move and labels every move with how
+/// far its expected score falls behind the best one.
+pub fn render(
+    parent: &mut ChildBuilder,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    font: Handle<Font>,
+    theme: &Theme,
+    layout: &Layout,
+    scores: &[MoveScore],
+) {
+    let best = ai::best(scores);
+    let best_score = scores
+        .iter()
+        .map(|score| score.score)
+        .fold(f64::MIN, f64::max);
+    let no_color = materials.add(Color::NONE.into());
+    let best_color = materials.add(theme.hint_best.into());
+    let other_color = materials.add(theme.hint_other.into());
+
+    parent
+        .spawn_bundle(NodeBundle {
+            style: root_style(layout),
+            material: no_color,
+            ..Default::default()
+        })
+        .insert(HintRoot)
+        .with_children(|parent| {
+            for direction in Direction::ALL.iter() {
+                let is_best = best == Some(*direction);
+                let behind = scores
+                    .iter()
+                    .find(|score| score.direction == *direction)
+                    .map(|score| score.score - best_score);
+                let (material, paint) = if is_best {
+                    (best_color.clone(), Paint::HintBest)
+                } else {
+                    (other_color.clone(), Paint::HintOther)
+                };
+                let strip = HintStrip(*direction);
+
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: strip_style(layout, &strip),
+                        material: material,
+                        ..Default::default()
+                    })
+                    .insert(strip)
+                    .insert(paint)
+                    .with_children(|parent| {
+                        parent
+                            .spawn_bundle(TextBundle {
+                                text: Text::with_section(
+                                    label(*direction, is_best, behind),
+                                    TextStyle {
+                                        font: font.clone(),
+                                        font_size: font_size(layout),
+                                        color: theme.overlay_text,
+                                    },
+                                    TextAlignment {
+                                        vertical: VerticalAlign::Center,
+                                        horizontal: HorizontalAlign::Center,
+                                    },
+                                ),
+                                ..Default::default()
+                            })
+                            .insert(Paint::OverlayText);
+                    });
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn side_strips_only_hold_the_score() {
+        assert_eq!(label(Direction::TOP, true, Some(0.0)), "Up best");
+        assert_eq!(label(Direction::BOTTOM, false, None), "Down -");
+        assert_eq!(label(Direction::LEFT, false, Some(-1234.4)), "-1234");
+        assert_eq!(label(Direction::RIGHT, true, Some(0.0)), "best");
+
+        // A move tied with the best one isn't highlighted, so it doesn't say best either
+        assert_eq!(label(Direction::LEFT, false, Some(0.0)), "0");
+        assert_eq!(label(Direction::BOTTOM, false, Some(0.0)), "Down 0");
+    }
+}
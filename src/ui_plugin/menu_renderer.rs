@@ -0,0 +1,137 @@
+use crate::ui_plugin::theme::{Paint, Theme};
+use crate::ui_plugin::win_renderer::render_button;
+use bevy::prelude::*;
+
+pub struct MenuRoot;
+pub struct PauseRoot;
+
+/// Choices offered by the main menu and the pause screen
+pub enum MenuButton {
+    NewGame,
+    Continue,
+    MainMenu,
+    Quit,
+}
+
+pub fn render_main_menu(
+    parent: &mut ChildBuilder,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    font: Handle<Font>,
+    theme: &Theme,
+) {
+    let background = materials.add(theme.background.into());
+    let button_color = materials.add(theme.button.into());
+
+    parent
+        .spawn_bundle(overlay(background))
+        .insert(MenuRoot)
+        .insert(Paint::Background)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(title("2048", theme.title_text, font.clone()))
+                .insert(Paint::TitleText);
+
+            render_button(
+                parent,
+                "New game (N)",
+                MenuButton::NewGame,
+                button_color.clone(),
+                font.clone(),
+                theme,
+            );
+            render_button(
+                parent,
+                "Continue (Return)",
+                MenuButton::Continue,
+                button_color.clone(),
+                font.clone(),
+                theme,
+            );
+            render_button(
+                parent,
+                "Quit (Esc)",
+                MenuButton::Quit,
+                button_color,
+                font.clone(),
+                theme,
+            );
+        });
+}
+
+pub fn render_pause(
+    parent: &mut ChildBuilder,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    font: Handle<Font>,
+    theme: &Theme,
+) {
+    let background = materials.add(theme.overlay.into());
+    let button_color = materials.add(theme.button.into());
+
+    parent
+        .spawn_bundle(overlay(background))
+        .insert(PauseRoot)
+        .insert(Paint::Overlay)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(title("Paused", theme.overlay_text, font.clone()))
+                .insert(Paint::OverlayText);
+
+            render_button(
+                parent,
+                "Resume (Esc)",
+                MenuButton::Continue,
+                button_color.clone(),
+                font.clone(),
+                theme,
+            );
+            render_button(
+                parent,
+                "New game (N)",
+                MenuButton::NewGame,
+                button_color.clone(),
+                font.clone(),
+                theme,
+            );
+            render_button(
+                parent,
+                "Main menu (M)",
+                MenuButton::MainMenu,
+                button_color,
+                font.clone(),
+                theme,
+            );
+        });
+}
+
+fn overlay(material: Handle<ColorMaterial>) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: material,
+        ..Default::default()
+    }
+}
+
+fn title(label: &str, color: Color, font: Handle<Font>) -> TextBundle {
+    TextBundle {
+        text: Text::with_section(
+            label,
+            TextStyle {
+                font: font,
+                font_size: 60.0,
+                color: color,
+            },
+            TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            },
+        ),
+        ..Default::default()
+    }
+}
@@ -1,65 +1,67 @@
 use crate::logic::number::Number;
-use crate::ui_plugin::CELL_SIZE;
+use crate::ui_plugin::theme::{Paint, Theme};
 use bevy::prelude::*;
 
 pub struct Block {
     pub id: i32,
+    pub number: Number,
 }
 
-impl Number {
-    pub fn color(&self) -> Color {
-        match self {
-            Number::ZERO => Color::rgb_u8(240, 228, 218),
-            Number::ONE => Color::rgb_u8(236, 224, 201),
-            Number::TWO => Color::rgb_u8(255, 178, 120),
-            Number::THREE => Color::rgb_u8(254, 150, 92),
-            Number::FOUR => Color::rgb_u8(247, 123, 97),
-            Number::FIVE => Color::rgb_u8(235, 88, 55),
-            Number::SIX => Color::rgb_u8(236, 220, 146),
-            Number::SEVEN => Color::rgb_u8(240, 212, 121),
-            Number::EIGHT => Color::rgb_u8(244, 206, 96),
-            Number::NINE => Color::rgb_u8(248, 200, 71),
-            Number::TEN => Color::rgb_u8(255, 194, 46),
-            Number::ELEVEN => Color::rgb_u8(104, 130, 249),
-            Number::TWELVE => Color::rgb_u8(51, 85, 247),
-            Number::THIRTEEN => Color::rgb_u8(10, 47, 222),
-            Number::FOURTEEN => Color::rgb_u8(9, 43, 202),
-            Number::FIFTEEN => Color::rgb_u8(181, 37, 188),
-            Number::SIXTEEN => Color::rgb_u8(166, 34, 172),
-        }
-    }
+/// Longer values are written as a power of two
+const MAX_LABEL_DIGITS: usize = 9;
 
-    pub fn text_color(&self) -> Color {
-        match self {
-            Number::ZERO | Number::ONE => Color::BLACK,
-            _ => Color::WHITE,
+/// How a block of each number looks on the board
+pub trait RenderNumber {
+    fn label(&self) -> String;
+    fn text_size(&self, cell_size: f32) -> f32;
+    fn render(
+        &self,
+        id: i32,
+        position: (f32, f32),
+        cell_size: f32,
+        parent: &mut ChildBuilder,
+        materials: &mut ResMut<Assets<ColorMaterial>>,
+        font: Handle<Font>,
+        theme: &Theme,
+    ) -> Entity;
+}
+
+impl RenderNumber for Number {
+    fn label(&self) -> String {
+        let text = self.to_string();
+        if text.len() > MAX_LABEL_DIGITS {
+            format!("2^{}", self.exponent())
+        } else {
+            text
         }
     }
 
-    pub fn text_size(&self) -> f32 {
-        use Number::*;
-        let multiplier: f32 = match self {
-            ZERO | ONE | TWO | THREE | FOUR | FIVE => 0.5,
-            SIX | SEVEN | EIGHT => 4.0 / 9.0,
-            NINE | TEN | ELEVEN | TWELVE => 2.0 / 5.0,
-            THIRTEEN | FOURTEEN | FIFTEEN => 7.0 / 20.0,
-            SIXTEEN => 3.0 / 10.0,
+    fn text_size(&self, cell_size: f32) -> f32 {
+        let length = self.label().len();
+        let multiplier: f32 = match length {
+            0..=2 => 0.5,
+            3 => 4.0 / 9.0,
+            4 => 2.0 / 5.0,
+            5 => 7.0 / 20.0,
+            _ => 1.8 / length as f32,
         };
-        CELL_SIZE * multiplier
+        cell_size * multiplier
     }
 
-    pub fn render(
+    fn render(
         &self,
         id: i32,
         position: (f32, f32),
+        cell_size: f32,
         parent: &mut ChildBuilder,
         materials: &mut ResMut<Assets<ColorMaterial>>,
         font: Handle<Font>,
-    ) {
+        theme: &Theme,
+    ) -> Entity {
         parent
             .spawn_bundle(NodeBundle {
                 style: Style {
-                    size: Size::new(Val::Px(CELL_SIZE), Val::Px(CELL_SIZE)),
+                    size: Size::new(Val::Px(cell_size), Val::Px(cell_size)),
                     border: Rect::all(Val::Px(1.0)),
                     position_type: PositionType::Absolute,
                     position: Rect {
@@ -71,17 +73,17 @@ impl Number {
                     align_items: AlignItems::Center,
                     ..Default::default()
                 },
-                material: materials.add(self.color().into()),
+                material: materials.add(theme.block(*self).into()),
                 ..Default::default()
             })
             .with_children(|cell| {
                 cell.spawn_bundle(TextBundle {
                     text: Text::with_section(
-                        format!("{}", self.value()),
+                        self.label(),
                         TextStyle {
                             font: font.clone(),
-                            font_size: self.text_size(),
-                            color: self.text_color(),
+                            font_size: self.text_size(cell_size),
+                            color: theme.block_text(*self),
                         },
                         TextAlignment {
                             vertical: VerticalAlign::Center,
@@ -89,8 +91,14 @@ impl Number {
                         },
                     ),
                     ..Default::default()
-                });
+                })
+                .insert(Paint::BlockText(*self));
+            })
+            .insert(Block {
+                id: id,
+                number: *self,
             })
-            .insert(Block { id: id });
+            .insert(Paint::Block(*self))
+            .id()
     }
 }
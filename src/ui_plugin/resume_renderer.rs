@@ -0,0 +1,81 @@
+use crate::ui_plugin::theme::{Paint, Theme};
+use bevy::prelude::*;
+
+pub struct ResumeRoot;
+
+pub fn render(
+    parent: &mut ChildBuilder,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    font: Handle<Font>,
+    theme: &Theme,
+    score: u64,
+) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.add(theme.overlay.into()),
+            ..Default::default()
+        })
+        .insert(Paint::Overlay)
+        .insert(ResumeRoot)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "Resume game?",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 60.0,
+                            color: theme.overlay_text,
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    ..Default::default()
+                })
+                .insert(Paint::OverlayText);
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        format!("Score: {}", score),
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 30.0,
+                            color: theme.overlay_text,
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    ..Default::default()
+                })
+                .insert(Paint::OverlayText);
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "Enter to continue, N for a new game",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 20.0,
+                            color: theme.overlay_text,
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    ..Default::default()
+                })
+                .insert(Paint::OverlayText);
+        });
+}
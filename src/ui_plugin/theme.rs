@@ -0,0 +1,434 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::logic::number::Number;
+
+/// Palette entry a node is painted with, so it can be repainted when the theme changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Paint {
+    Background,
+    Field,
+    EmptyCell,
+    Logo,
+    ScoreBox,
+    HeaderText,
+    /// Points floating up from the score box
+    PopupText,
+    /// Title of the main menu
+    TitleText,
+    Overlay,
+    WinOverlay,
+    OverlayText,
+    Button,
+    HintBest,
+    HintOther,
+    Block(Number),
+    BlockText(Number),
+}
+
+/// Every colour the game is drawn with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub background: Color,
+    pub field: Color,
+    pub empty_cell: Color,
+    pub logo: Color,
+    pub score_box: Color,
+    pub header_text: Color,
+    pub popup_text: Color,
+    pub title_text: Color,
+    pub overlay: Color,
+    pub win_overlay: Color,
+    pub overlay_text: Color,
+    pub button: Color,
+    pub hint_best: Color,
+    pub hint_other: Color,
+    /// Blocks from 2 upwards, later blocks get a hue of their own
+    pub blocks: Vec<Color>,
+    /// Text on the blocks up to `2^dark_text_up_to`
+    pub dark_block_text: Color,
+    pub light_block_text: Color,
+    pub dark_text_up_to: u32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+fn rgb(hex: u32) -> Color {
+    Color::rgb_u8((hex >> 16) as u8, (hex >> 8) as u8, hex as u8)
+}
+
+/// `steps` colours evenly spread along the line through `stops`
+fn gradient(stops: &[u32], steps: usize) -> Vec<Color> {
+    let stops: Vec<Vec4> = stops.iter().map(|hex| Vec4::from(rgb(*hex))).collect();
+    (0..steps)
+        .map(|step| {
+            let along = step as f32 / (steps - 1) as f32 * (stops.len() - 1) as f32;
+            let index = (along as usize).min(stops.len() - 2);
+            Color::from(stops[index].lerp(stops[index + 1], along - index as f32))
+        })
+        .collect()
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            background: Color::rgb(0.992, 0.969, 0.941),
+            field: Color::rgb(0.725, 0.675, 0.627),
+            empty_cell: Color::rgb(0.808, 0.753, 0.698),
+            logo: Color::rgb(0.929, 0.778, 0.012),
+            score_box: Color::rgb(0.725, 0.675, 0.627),
+            header_text: Color::WHITE,
+            popup_text: Color::rgb_u8(119, 110, 101),
+            title_text: Color::rgb_u8(119, 110, 101),
+            overlay: Color::rgba_u8(0, 0, 0, 180),
+            win_overlay: Color::rgba_u8(237, 194, 46, 160),
+            overlay_text: Color::WHITE,
+            button: Color::rgb(0.561, 0.478, 0.4),
+            hint_best: Color::rgba_u8(246, 124, 95, 200),
+            hint_other: Color::rgba_u8(0, 0, 0, 90),
+            blocks: [
+                0xf0e4da, 0xece0c9, 0xffb278, 0xfe965c, 0xf77b61, 0xeb5837, 0xecdc92, 0xf0d479,
+                0xf4ce60, 0xf8c847, 0xffc22e, 0x6882f9, 0x3355f7, 0x0a2fde, 0x092bca, 0xb525bc,
+                0xa622ac,
+            ]
+            .iter()
+            .map(|hex| rgb(*hex))
+            .collect(),
+            dark_block_text: Color::BLACK,
+            light_block_text: Color::WHITE,
+            dark_text_up_to: 2,
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            name: "dark".to_string(),
+            background: rgb(0x1e1d1b),
+            field: rgb(0x3a3733),
+            empty_cell: rgb(0x4a4641),
+            logo: rgb(0xc9a227),
+            score_box: rgb(0x3a3733),
+            header_text: rgb(0xf2eee9),
+            popup_text: rgb(0xd8cfc4),
+            title_text: rgb(0xd8cfc4),
+            overlay: Color::rgba_u8(0, 0, 0, 200),
+            win_overlay: Color::rgba_u8(120, 96, 20, 200),
+            button: rgb(0x6b5d50),
+            blocks: gradient(
+                &[0x6b6259, 0xb8693f, 0xc0443a, 0xc9a227, 0x3e63c4, 0x8e2f9c],
+                17,
+            ),
+            dark_block_text: rgb(0xf2eee9),
+            light_block_text: rgb(0xf2eee9),
+            ..Self::light()
+        }
+    }
+
+    /// Black and white around blocks that differ strongly in brightness and hue
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "high-contrast".to_string(),
+            background: Color::BLACK,
+            field: Color::WHITE,
+            empty_cell: Color::BLACK,
+            logo: rgb(0xffeb00),
+            score_box: Color::WHITE,
+            header_text: Color::BLACK,
+            popup_text: Color::WHITE,
+            title_text: Color::WHITE,
+            overlay: Color::rgba_u8(0, 0, 0, 230),
+            win_overlay: Color::rgba_u8(0, 0, 0, 230),
+            button: rgb(0x0050ff),
+            hint_best: rgb(0xffeb00),
+            hint_other: rgb(0x404040),
+            blocks: gradient(
+                &[0xffffff, 0xffeb00, 0xff8c00, 0x00c8ff, 0x0050ff, 0xc800c8],
+                17,
+            ),
+            dark_block_text: Color::BLACK,
+            light_block_text: Color::WHITE,
+            dark_text_up_to: 9,
+            ..Self::light()
+        }
+    }
+
+    /// Runs from yellow to blue, which stay apart without telling red from green
+    pub fn deuteranopia() -> Self {
+        Self {
+            name: "deuteranopia".to_string(),
+            logo: rgb(0xf0c808),
+            hint_best: Color::rgba_u8(0, 114, 178, 200),
+            blocks: gradient(&[0xfff3b0, 0xf0c808, 0x9a9a9a, 0x3b6fb6, 0x0b2a6b], 17),
+            dark_text_up_to: 4,
+            ..Self::light()
+        }
+    }
+
+    /// Like the deuteranopia theme but through orange, as reds look dark to protans
+    pub fn protanopia() -> Self {
+        Self {
+            name: "protanopia".to_string(),
+            logo: rgb(0xe69f00),
+            hint_best: Color::rgba_u8(86, 180, 233, 200),
+            blocks: gradient(&[0xe6f0ff, 0x56b4e9, 0x0072b2, 0xe69f00, 0x7a4a00], 17),
+            dark_text_up_to: 3,
+            ..Self::light()
+        }
+    }
+
+    /// The built in themes, in the order they are switched through
+    pub fn built_in() -> Vec<Self> {
+        vec![
+            Self::light(),
+            Self::dark(),
+            Self::high_contrast(),
+            Self::deuteranopia(),
+            Self::protanopia(),
+        ]
+    }
+
+    /// A built in theme by name, or else a theme file
+    pub fn load(name_or_path: &str) -> Result<Self, String> {
+        if let Some(theme) = Self::built_in()
+            .into_iter()
+            .find(|theme| theme.name == name_or_path)
+        {
+            return Ok(theme);
+        }
+
+        let contents = fs::read_to_string(Path::new(name_or_path)).map_err(|error| {
+            format!(
+                "{} is neither a theme (light, dark, high-contrast, deuteranopia, protanopia) nor a readable file: {}",
+                name_or_path, error
+            )
+        })?;
+        Self::from_json(&contents)
+    }
+
+    /// Reads a theme such as
+    /// `{"name": "mine", "base": "dark", "colors": {"logo": "#ff8800"}, "blocks": ["#202020"]}`
+    /// where the listed colours replace those of the base theme, blocks from 2 upwards
+    pub fn from_json(contents: &str) -> Result<Self, String> {
+        let file: ThemeFile = serde_json::from_str(contents).map_err(|error| error.to_string())?;
+        let base = file.base.as_deref().unwrap_or("light");
+        let mut theme = Self::built_in()
+            .into_iter()
+            .find(|theme| theme.name == base)
+            .ok_or_else(|| format!("Unknown base theme {}", base))?;
+
+        theme.name = file.name.unwrap_or_else(|| "custom".to_string());
+        for (entry, value) in file.colors.iter() {
+            let color = parse_color(value)?;
+            *theme
+                .entry_mut(entry)
+                .ok_or_else(|| format!("Unknown colour {}", entry))? = color;
+        }
+        for (index, value) in file.blocks.iter().enumerate() {
+            let color = parse_color(value)?;
+            match theme.blocks.get_mut(index) {
+                Some(block) => *block = color,
+                None => theme.blocks.push(color),
+            }
+        }
+        if let Some(dark_text_up_to) = file.dark_text_up_to {
+            theme.dark_text_up_to = dark_text_up_to;
+        }
+
+        Ok(theme)
+    }
+
+    fn entry_mut(&mut self, name: &str) -> Option<&mut Color> {
+        let entry = match name {
+            "background" => &mut self.background,
+            "field" => &mut self.field,
+            "empty_cell" => &mut self.empty_cell,
+            "logo" => &mut self.logo,
+            "score_box" => &mut self.score_box,
+            "header_text" => &mut self.header_text,
+            "popup_text" => &mut self.popup_text,
+            "title_text" => &mut self.title_text,
+            "overlay" => &mut self.overlay,
+            "win_overlay" => &mut self.win_overlay,
+            "overlay_text" => &mut self.overlay_text,
+            "button" => &mut self.button,
+            "hint_best" => &mut self.hint_best,
+            "hint_other" => &mut self.hint_other,
+            "dark_block_text" => &mut self.dark_block_text,
+            "light_block_text" => &mut self.light_block_text,
+            _ => return None,
+        };
+        Some(entry)
+    }
+
+    pub fn block(&self, number: Number) -> Color {
+        let index = number.exponent().saturating_sub(1) as usize;
+        if let Some(color) = self.blocks.get(index) {
+            return *color;
+        }
+
+        // Past the table each block gets its own hue and grows darker
+        let step = (index - self.blocks.len()) as f32;
+        let hue = (300.0 + step * 137.5) % 360.0;
+        let lightness = f32::max(0.45 - step * 0.01, 0.25);
+        Color::hsl(hue, 0.7, lightness)
+    }
+
+    pub fn block_text(&self, number: Number) -> Color {
+        if number.exponent() <= self.dark_text_up_to {
+            self.dark_block_text
+        } else {
+            self.light_block_text
+        }
+    }
+
+    pub fn color(&self, paint: Paint) -> Color {
+        match paint {
+            Paint::Background => self.background,
+            Paint::Field => self.field,
+            Paint::EmptyCell => self.empty_cell,
+            Paint::Logo => self.logo,
+            Paint::ScoreBox => self.score_box,
+            Paint::HeaderText => self.header_text,
+            Paint::PopupText => self.popup_text,
+            Paint::TitleText => self.title_text,
+            Paint::Overlay => self.overlay,
+            Paint::WinOverlay => self.win_overlay,
+            Paint::OverlayText => self.overlay_text,
+            Paint::Button => self.button,
+            Paint::HintBest => self.hint_best,
+            Paint::HintOther => self.hint_other,
+            Paint::Block(number) => self.block(number),
+            Paint::BlockText(number) => self.block_text(number),
+        }
+    }
+
+    /// The theme after this one among `themes`, wrapping around
+    pub fn next<'a>(&self, themes: &'a [Theme]) -> &'a Theme {
+        let index = themes
+            .iter()
+            .position(|theme| theme.name == self.name)
+            .map_or(0, |index| (index + 1) % themes.len());
+        &themes[index]
+    }
+}
+
+/// `#rrggbb` or `#rrggbbaa`
+fn parse_color(value: &str) -> Result<Color, String> {
+    let invalid = || format!("Invalid colour {}, expected #rrggbb or #rrggbbaa", value);
+    let hex = value.strip_prefix('#').ok_or_else(invalid)?;
+    // Checked before slicing by bytes, which would panic inside a multi-byte character
+    if !hex.is_ascii() || (hex.len() != 6 && hex.len() != 8) {
+        return Err(invalid());
+    }
+
+    let channels = (0..hex.len())
+        .step_by(2)
+        .map(|start| u8::from_str_radix(&hex[start..start + 2], 16).map_err(|_| invalid()))
+        .collect::<Result<Vec<u8>, String>>()?;
+    let alpha = channels.get(3).copied().unwrap_or(255);
+    Ok(Color::rgba_u8(channels[0], channels[1], channels[2], alpha))
+}
+
+/// Layout of a theme file, everything is optional
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    name: Option<String>,
+    base: Option<String>,
+    #[serde(default)]
+    colors: BTreeMap<String, String>,
+    #[serde(default)]
+    blocks: Vec<String>,
+    dark_text_up_to: Option<u32>,
+}
+
+/// Themes switched through with a key, the built in ones and any loaded from a file
+pub struct ThemeChoices {
+    pub themes: Vec<Theme>,
+}
+
+impl ThemeChoices {
+    pub fn with(theme: &Theme) -> Self {
+        let mut themes = Theme::built_in();
+        if !themes.iter().any(|built_in| built_in.name == theme.name) {
+            themes.push(theme.clone());
+        }
+        Self { themes: themes }
+    }
+}
+
+/// Repaints every node with the colours of the new theme
+pub fn recolor(
+    theme: Res<Theme>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    painted: Query<(&Paint, &Handle<ColorMaterial>)>,
+    mut texts: Query<(&Paint, &mut Text)>,
+) {
+    if !theme.is_changed() || theme.is_added() {
+        return;
+    }
+
+    for (paint, handle) in painted.iter() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.color = theme.color(*paint);
+        }
+    }
+    for (paint, mut text) in texts.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.style.color = theme.color(*paint);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_themes_have_distinct_names_and_every_block() {
+        let themes = Theme::built_in();
+
+        for theme in themes.iter() {
+            assert_eq!(theme.blocks.len(), 17, "{}", theme.name);
+            assert_eq!(Theme::load(&theme.name).unwrap(), *theme);
+            assert_eq!(
+                themes
+                    .iter()
+                    .filter(|other| other.name == theme.name)
+                    .count(),
+                1
+            );
+        }
+        assert_eq!(Theme::light().next(&themes).name, "dark");
+        assert_eq!(Theme::protanopia().next(&themes).name, "light");
+    }
+
+    #[test]
+    fn theme_file_overrides_its_base() {
+        let theme = Theme::from_json(
+            r##"{"name": "mine", "base": "dark", "colors": {"logo": "#ff880080"}, "blocks": ["#000000"]}"##,
+        )
+        .unwrap();
+
+        assert_eq!(theme.name, "mine");
+        assert_eq!(theme.logo, Color::rgba_u8(255, 136, 0, 128));
+        assert_eq!(theme.blocks[0], Color::BLACK);
+        assert_eq!(theme.blocks[1], Theme::dark().blocks[1]);
+        assert_eq!(theme.field, Theme::dark().field);
+
+        assert!(Theme::from_json(r##"{"colors": {"sky": "#000000"}}"##).is_err());
+        assert!(Theme::from_json(r#"{"colors": {"logo": "orange"}}"#).is_err());
+        assert!(Theme::from_json(r##"{"colors": {"logo": "#aébcd"}}"##).is_err());
+        assert!(Theme::from_json(r#"{"base": "sepia"}"#).is_err());
+    }
+}
@@ -0,0 +1,116 @@
+use crate::ui_plugin::theme::{Paint, Theme};
+use bevy::ecs::component::Component;
+use bevy::prelude::*;
+
+pub struct WinRoot;
+
+/// Choices offered once the game is won
+pub enum WinButton {
+    KeepGoing,
+    NewGame,
+}
+
+pub fn render(
+    parent: &mut ChildBuilder,
+    materials: &mut ResMut<Assets<ColorMaterial>>,
+    font: Handle<Font>,
+    theme: &Theme,
+) {
+    let button_color = materials.add(theme.button.into());
+
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.add(theme.win_overlay.into()),
+            ..Default::default()
+        })
+        .insert(Paint::WinOverlay)
+        .insert(WinRoot)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "You win!",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 60.0,
+                            color: theme.overlay_text,
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    ..Default::default()
+                })
+                .insert(Paint::OverlayText);
+
+            render_button(
+                parent,
+                "Keep going (K)",
+                WinButton::KeepGoing,
+                button_color.clone(),
+                font.clone(),
+                theme,
+            );
+            render_button(
+                parent,
+                "New game (Space)",
+                WinButton::NewGame,
+                button_color,
+                font.clone(),
+                theme,
+            );
+        });
+}
+
+/// A labelled button carrying `button` so clicks can be told apart
+pub fn render_button<T: Component>(
+    parent: &mut ChildBuilder,
+    label: &str,
+    button: T,
+    color: Handle<ColorMaterial>,
+    font: Handle<Font>,
+    theme: &Theme,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(240.0), Val::Px(50.0)),
+                margin: Rect::all(Val::Px(10.0)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: color,
+            ..Default::default()
+        })
+        .insert(button)
+        .insert(Paint::Button)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        label,
+                        TextStyle {
+                            font: font,
+                            font_size: 25.0,
+                            color: theme.overlay_text,
+                        },
+                        TextAlignment {
+                            vertical: VerticalAlign::Center,
+                            horizontal: HorizontalAlign::Center,
+                        },
+                    ),
+                    ..Default::default()
+                })
+                .insert(Paint::OverlayText);
+        });
+}